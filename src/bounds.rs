@@ -0,0 +1,318 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+
+// An axis-aligned box, used to skip shapes a ray can't possibly hit. Either
+// end may be infinite, as for planes and untruncated cylinders.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+  min: Tuple,
+  max: Tuple,
+}
+
+impl Default for BoundingBox {
+  fn default() -> BoundingBox {
+    BoundingBox::empty()
+  }
+}
+
+fn axes(t: Tuple) -> [f32; 3] {
+  [t.x(), t.y(), t.z()]
+}
+
+impl BoundingBox {
+  pub fn new(min: Tuple, max: Tuple) -> BoundingBox {
+    BoundingBox { min, max }
+  }
+
+  // Contains nothing, so adding anything to it gives that thing's bounds.
+  pub fn empty() -> BoundingBox {
+    BoundingBox {
+      min: Tuple::point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+      max: Tuple::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    }
+  }
+
+  pub fn min(&self) -> Tuple {
+    self.min
+  }
+
+  pub fn max(&self) -> Tuple {
+    self.max
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+  }
+
+  pub fn add_point(&mut self, point: Tuple) {
+    self.min = Tuple::point(self.min.x().min(point.x()), self.min.y().min(point.y()), self.min.z().min(point.z()));
+    self.max = Tuple::point(self.max.x().max(point.x()), self.max.y().max(point.y()), self.max.z().max(point.z()));
+  }
+
+  pub fn add_box(&mut self, other: &BoundingBox) {
+    if !other.is_empty() {
+      self.add_point(other.min);
+      self.add_point(other.max);
+    }
+  }
+
+  pub fn contains_point(&self, point: Tuple) -> bool {
+    (self.min.x()..=self.max.x()).contains(&point.x()) &&
+      (self.min.y()..=self.max.y()).contains(&point.y()) &&
+      (self.min.z()..=self.max.z()).contains(&point.z())
+  }
+
+  pub fn contains_box(&self, other: &BoundingBox) -> bool {
+    self.contains_point(other.min) && self.contains_point(other.max)
+  }
+
+  // The box around this one once transformed. Rather than transform all eight
+  // corners, each output axis takes the smaller and larger contribution of
+  // every input axis, which also keeps infinite extents from turning into NaN
+  // where the matrix has a zero.
+  pub fn transform(&self, m: &Mat4) -> BoundingBox {
+    if self.is_empty() {
+      return *self;
+    }
+
+    let (min, max) = (axes(self.min), axes(self.max));
+    let mut new_min = [0.0; 3];
+    let mut new_max = [0.0; 3];
+
+    for row in 0..3 {
+      new_min[row] = m[(row, 3)];
+      new_max[row] = m[(row, 3)];
+      for col in 0..3 {
+        let factor = m[(row, col)];
+        if factor == 0.0 {
+          continue;
+        }
+        let (a, b) = (factor * min[col], factor * max[col]);
+        new_min[row] += a.min(b);
+        new_max[row] += a.max(b);
+      }
+    }
+
+    BoundingBox::new(
+      Tuple::point(new_min[0], new_min[1], new_min[2]),
+      Tuple::point(new_max[0], new_max[1], new_max[2]))
+  }
+
+  // The same slab test as Cube, against this box's extents.
+  pub fn intersects(&self, ray: &Ray) -> bool {
+    if self.is_empty() {
+      return false;
+    }
+
+    let (origin, direction) = (axes(ray.origin()), axes(ray.direction()));
+    let (min, max) = (axes(self.min), axes(self.max));
+    let mut tmin = f32::NEG_INFINITY;
+    let mut tmax = f32::INFINITY;
+
+    for axis in 0..3 {
+      let (t0, t1) = check_axis(origin[axis], direction[axis], min[axis], max[axis]);
+      tmin = tmin.max(t0);
+      tmax = tmax.min(t1);
+    }
+
+    tmin <= tmax
+  }
+
+  // Halves the box across its longest axis.
+  pub fn split(&self) -> (BoundingBox, BoundingBox) {
+    let (min, max) = (axes(self.min), axes(self.max));
+    let sizes = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+    let greatest = sizes.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let axis = sizes.iter().position(|s| *s == greatest).unwrap_or(0);
+
+    let mid = min[axis] + sizes[axis] / 2.0;
+    let mut mid_min = min;
+    let mut mid_max = max;
+    mid_min[axis] = mid;
+    mid_max[axis] = mid;
+
+    let left = BoundingBox::new(self.min, Tuple::point(mid_max[0], mid_max[1], mid_max[2]));
+    let right = BoundingBox::new(Tuple::point(mid_min[0], mid_min[1], mid_min[2]), self.max);
+    (left, right)
+  }
+}
+
+fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+  // A ray parallel to the slab is either always or never between its sides.
+  if direction.abs() < float::EPSILON {
+    return if min <= origin && origin <= max {
+      (f32::NEG_INFINITY, f32::INFINITY)
+    } else {
+      (f32::INFINITY, f32::NEG_INFINITY)
+    };
+  }
+
+  let t0 = (min - origin) / direction;
+  let t1 = (max - origin) / direction;
+  if t0 > t1 {
+    (t1, t0)
+  } else {
+    (t0, t1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::{PI, SQRT_2};
+
+  #[test]
+  fn test_empty() {
+    let b = BoundingBox::empty();
+    assert!(b.is_empty());
+    assert_eq!(b.min(), Tuple::point(f32::INFINITY, f32::INFINITY, f32::INFINITY));
+    assert_eq!(b.max(), Tuple::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY));
+  }
+
+  #[test]
+  fn test_add_point() {
+    let mut b = BoundingBox::empty();
+    b.add_point(Tuple::point(-5.0, 2.0, 0.0));
+    b.add_point(Tuple::point(7.0, 0.0, -3.0));
+
+    assert_eq!(b.min(), Tuple::point(-5.0, 0.0, -3.0));
+    assert_eq!(b.max(), Tuple::point(7.0, 2.0, 0.0));
+  }
+
+  #[test]
+  fn test_add_box() {
+    let mut b1 = BoundingBox::new(Tuple::point(-5.0, -2.0, 0.0), Tuple::point(7.0, 4.0, 4.0));
+    let b2 = BoundingBox::new(Tuple::point(8.0, -7.0, -2.0), Tuple::point(14.0, 2.0, 8.0));
+    b1.add_box(&b2);
+    b1.add_box(&BoundingBox::empty());
+
+    assert_eq!(b1.min(), Tuple::point(-5.0, -7.0, -2.0));
+    assert_eq!(b1.max(), Tuple::point(14.0, 4.0, 8.0));
+  }
+
+  #[test]
+  fn test_contains_point() {
+    let b = BoundingBox::new(Tuple::point(5.0, -2.0, 0.0), Tuple::point(11.0, 4.0, 7.0));
+    let examples = [
+      (Tuple::point(5.0, -2.0, 0.0), true),
+      (Tuple::point(11.0, 4.0, 7.0), true),
+      (Tuple::point(8.0, 1.0, 3.0), true),
+      (Tuple::point(3.0, 0.0, 3.0), false),
+      (Tuple::point(8.0, -4.0, 3.0), false),
+      (Tuple::point(8.0, 1.0, -1.0), false),
+      (Tuple::point(13.0, 1.0, 3.0), false),
+      (Tuple::point(8.0, 5.0, 3.0), false),
+      (Tuple::point(8.0, 1.0, 8.0), false),
+    ];
+
+    for (point, result) in examples.iter() {
+      assert_eq!(b.contains_point(*point), *result);
+    }
+  }
+
+  #[test]
+  fn test_contains_box() {
+    let b = BoundingBox::new(Tuple::point(5.0, -2.0, 0.0), Tuple::point(11.0, 4.0, 7.0));
+    let examples = [
+      (Tuple::point(5.0, -2.0, 0.0), Tuple::point(11.0, 4.0, 7.0), true),
+      (Tuple::point(6.0, -1.0, 1.0), Tuple::point(10.0, 3.0, 6.0), true),
+      (Tuple::point(4.0, -3.0, -1.0), Tuple::point(10.0, 3.0, 6.0), false),
+      (Tuple::point(6.0, -1.0, 1.0), Tuple::point(12.0, 5.0, 8.0), false),
+    ];
+
+    for (min, max, result) in examples.iter() {
+      assert_eq!(b.contains_box(&BoundingBox::new(*min, *max)), *result);
+    }
+  }
+
+  #[test]
+  fn test_transform() {
+    let b = BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0));
+    let t = b.transform(&(Mat4::rotation_x(PI / 4.0) * Mat4::rotation_y(PI / 4.0)));
+
+    assert_eq!(t.min(), Tuple::point(-SQRT_2, -1.70710, -1.70710));
+    assert_eq!(t.max(), Tuple::point(SQRT_2, 1.70710, 1.70710));
+  }
+
+  #[test]
+  fn test_transform_infinite() {
+    let b = BoundingBox::new(
+      Tuple::point(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+      Tuple::point(f32::INFINITY, 0.0, f32::INFINITY));
+    let t = b.transform(&Mat4::translation(0.0, 2.0, 0.0));
+
+    assert_eq!(t.min().y(), 2.0);
+    assert_eq!(t.max().y(), 2.0);
+    assert_eq!(t.min().x(), f32::NEG_INFINITY);
+  }
+
+  #[test]
+  fn test_intersects_cube() {
+    let b = BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0));
+    let examples = [
+      (Tuple::point(5.0, 0.5, 0.0), Tuple::vector(-1.0, 0.0, 0.0), true),
+      (Tuple::point(-5.0, 0.5, 0.0), Tuple::vector(1.0, 0.0, 0.0), true),
+      (Tuple::point(0.5, 5.0, 0.0), Tuple::vector(0.0, -1.0, 0.0), true),
+      (Tuple::point(0.5, -5.0, 0.0), Tuple::vector(0.0, 1.0, 0.0), true),
+      (Tuple::point(0.5, 0.0, 5.0), Tuple::vector(0.0, 0.0, -1.0), true),
+      (Tuple::point(0.5, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), true),
+      (Tuple::point(0.0, 0.5, 0.0), Tuple::vector(0.0, 0.0, 1.0), true),
+      (Tuple::point(-2.0, 0.0, 0.0), Tuple::vector(2.0, 4.0, 6.0), false),
+      (Tuple::point(0.0, -2.0, 0.0), Tuple::vector(6.0, 2.0, 4.0), false),
+      (Tuple::point(0.0, 0.0, -2.0), Tuple::vector(4.0, 6.0, 2.0), false),
+      (Tuple::point(2.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, -1.0), false),
+      (Tuple::point(0.0, 2.0, 2.0), Tuple::vector(0.0, -1.0, 0.0), false),
+      (Tuple::point(2.0, 2.0, 0.0), Tuple::vector(-1.0, 0.0, 0.0), false),
+    ];
+
+    for (origin, direction, result) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert_eq!(b.intersects(&r), *result);
+    }
+  }
+
+  #[test]
+  fn test_intersects_non_cubic() {
+    let b = BoundingBox::new(Tuple::point(5.0, -2.0, 0.0), Tuple::point(11.0, 4.0, 7.0));
+    let examples = [
+      (Tuple::point(15.0, 1.0, 2.0), Tuple::vector(-1.0, 0.0, 0.0), true),
+      (Tuple::point(-5.0, -1.0, 4.0), Tuple::vector(1.0, 0.0, 0.0), true),
+      (Tuple::point(7.0, 6.0, 5.0), Tuple::vector(0.0, -1.0, 0.0), true),
+      (Tuple::point(9.0, -5.0, 6.0), Tuple::vector(0.0, 1.0, 0.0), true),
+      (Tuple::point(8.0, 2.0, 12.0), Tuple::vector(0.0, 0.0, -1.0), true),
+      (Tuple::point(6.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), true),
+      (Tuple::point(8.0, 1.0, 3.5), Tuple::vector(0.0, 0.0, 1.0), true),
+      (Tuple::point(9.0, -1.0, -8.0), Tuple::vector(2.0, 4.0, 6.0), false),
+      (Tuple::point(8.0, 3.0, -4.0), Tuple::vector(6.0, 2.0, 4.0), false),
+      (Tuple::point(9.0, -1.0, -2.0), Tuple::vector(4.0, 6.0, 2.0), false),
+      (Tuple::point(4.0, 0.0, 9.0), Tuple::vector(0.0, 0.0, -1.0), false),
+      (Tuple::point(8.0, 6.0, -1.0), Tuple::vector(0.0, -1.0, 0.0), false),
+      (Tuple::point(12.0, 5.0, 4.0), Tuple::vector(-1.0, 0.0, 0.0), false),
+    ];
+
+    for (origin, direction, result) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert_eq!(b.intersects(&r), *result);
+    }
+  }
+
+  #[test]
+  fn test_split() {
+    let examples = [
+      (Tuple::point(-1.0, -4.0, -5.0), Tuple::point(9.0, 6.0, 5.0), Tuple::point(4.0, 6.0, 5.0), Tuple::point(4.0, -4.0, -5.0)),
+      (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(9.0, 5.5, 3.0), Tuple::point(4.0, 5.5, 3.0), Tuple::point(4.0, -2.0, -3.0)),
+      (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 8.0, 3.0), Tuple::point(5.0, 3.0, 3.0), Tuple::point(-1.0, 3.0, -3.0)),
+      (Tuple::point(-1.0, -2.0, -3.0), Tuple::point(5.0, 3.0, 7.0), Tuple::point(5.0, 3.0, 2.0), Tuple::point(-1.0, -2.0, 2.0)),
+    ];
+
+    for (min, max, left_max, right_min) in examples.iter() {
+      let (left, right) = BoundingBox::new(*min, *max).split();
+      assert_eq!(left.min(), *min);
+      assert_eq!(left.max(), *left_max);
+      assert_eq!(right.min(), *right_min);
+      assert_eq!(right.max(), *max);
+    }
+  }
+}
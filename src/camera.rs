@@ -0,0 +1,218 @@
+use super::tuple::Tuple;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::ray::Ray;
+use super::canvas::Canvas;
+use super::world::World;
+use super::color::Color;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// A pinhole camera one unit in front of a canvas of hsize by vsize pixels.
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+  hsize: usize,
+  vsize: usize,
+  field_of_view: f32,
+  transform: Transform,
+  half_width: f32,
+  half_height: f32,
+  pixel_size: f32,
+}
+
+impl Camera {
+  pub fn new(hsize: usize, vsize: usize, field_of_view: f32) -> Camera {
+    let half_view = (field_of_view / 2.0).tan();
+    let aspect = hsize as f32 / vsize as f32;
+
+    let (half_width, half_height) = if aspect >= 1.0 {
+      (half_view, half_view / aspect)
+    } else {
+      (half_view * aspect, half_view)
+    };
+
+    Camera {
+      hsize,
+      vsize,
+      field_of_view,
+      transform: Transform::identity(),
+      half_width,
+      half_height,
+      pixel_size: (half_width * 2.0) / hsize as f32,
+    }
+  }
+
+  pub fn hsize(&self) -> usize {
+    self.hsize
+  }
+
+  pub fn vsize(&self) -> usize {
+    self.vsize
+  }
+
+  pub fn field_of_view(&self) -> f32 {
+    self.field_of_view
+  }
+
+  pub fn pixel_size(&self) -> f32 {
+    self.pixel_size
+  }
+
+  pub fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  pub fn set_transform(&mut self, m: Mat4) {
+    self.transform = Transform::new(m);
+  }
+
+  pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+    // Offsets from the edge of the canvas to the pixel's centre.
+    let xoffset = (x as f32 + 0.5) * self.pixel_size;
+    let yoffset = (y as f32 + 0.5) * self.pixel_size;
+
+    // The camera looks toward -z, so +x is to the left.
+    let world_x = self.half_width - xoffset;
+    let world_y = self.half_height - yoffset;
+
+    let inverse = *self.transform.inverse();
+    let pixel = inverse * Tuple::point(world_x, world_y, -1.0);
+    let origin = inverse * Tuple::point(0.0, 0.0, 0.0);
+    let direction = (pixel - origin).normalize();
+
+    Ray::new(origin, direction)
+  }
+
+  // Renders on every available core.
+  pub fn render(&self, world: &World) -> Canvas {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    self.render_with_threads(world, threads)
+  }
+
+  // Threads take rows in turn from a shared counter, so a slow row only holds
+  // up the thread working on it. Every pixel is worked out on its own from the
+  // same inputs, so the image doesn't depend on how many threads there are or
+  // which one rendered which row.
+  pub fn render_with_threads(&self, world: &World, threads: usize) -> Canvas {
+    let image = Mutex::new(Canvas::new(self.hsize, self.vsize));
+    let next_row = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+      for _ in 0..threads.clamp(1, self.vsize.max(1)) {
+        scope.spawn(|| {
+          let mut row = Vec::with_capacity(self.hsize);
+          loop {
+            let y = next_row.fetch_add(1, Ordering::Relaxed);
+            if y >= self.vsize {
+              break;
+            }
+
+            row.clear();
+            row.extend((0..self.hsize).map(|x| self.render_pixel(world, x, y)));
+
+            let mut image = image.lock().unwrap();
+            for (x, color) in row.iter().enumerate() {
+              image[(x, y)] = *color;
+            }
+          }
+        });
+      }
+    });
+
+    image.into_inner().unwrap()
+  }
+
+  fn render_pixel(&self, world: &World, x: usize, y: usize) -> Color {
+    let ray = self.ray_for_pixel(x, y);
+    world.color_at(&ray, world.max_depth())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+  use super::super::world::default_world;
+  use std::f32::consts::{PI, FRAC_1_SQRT_2};
+
+  #[test]
+  fn test_new() {
+    let c = Camera::new(160, 120, PI / 2.0);
+    assert_eq!(c.hsize(), 160);
+    assert_eq!(c.vsize(), 120);
+    assert!(float::eq(c.field_of_view(), PI / 2.0));
+    assert_eq!(*c.transform().matrix(), Mat4::identity());
+  }
+
+  #[test]
+  fn test_pixel_size_horizontal() {
+    let c = Camera::new(200, 125, PI / 2.0);
+    assert!(float::eq(c.pixel_size(), 0.01));
+  }
+
+  #[test]
+  fn test_pixel_size_vertical() {
+    let c = Camera::new(125, 200, PI / 2.0);
+    assert!(float::eq(c.pixel_size(), 0.01));
+  }
+
+  #[test]
+  fn test_ray_for_pixel_centre() {
+    let c = Camera::new(201, 101, PI / 2.0);
+    let r = c.ray_for_pixel(100, 50);
+    assert_eq!(r.origin(), Tuple::point(0.0, 0.0, 0.0));
+    assert_eq!(r.direction(), Tuple::vector(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_ray_for_pixel_corner() {
+    let c = Camera::new(201, 101, PI / 2.0);
+    let r = c.ray_for_pixel(0, 0);
+    assert_eq!(r.origin(), Tuple::point(0.0, 0.0, 0.0));
+    assert_eq!(r.direction(), Tuple::vector(0.66519, 0.33259, -0.66851));
+  }
+
+  #[test]
+  fn test_ray_for_pixel_transformed() {
+    let mut c = Camera::new(201, 101, PI / 2.0);
+    c.set_transform(Mat4::rotation_y(PI / 4.0) * Mat4::translation(0.0, -2.0, 5.0));
+    let r = c.ray_for_pixel(100, 50);
+    assert_eq!(r.origin(), Tuple::point(0.0, 2.0, -5.0));
+    assert_eq!(r.direction(), Tuple::vector(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2));
+  }
+
+  #[test]
+  fn test_render() {
+    let w = default_world();
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    let from = Tuple::point(0.0, 0.0, -5.0);
+    let to = Tuple::point(0.0, 0.0, 0.0);
+    let up = Tuple::vector(0.0, 1.0, 0.0);
+    c.set_transform(Mat4::view_transform(from, to, up));
+
+    let image = c.render(&w);
+    assert_eq!(image[(5, 5)], Color::new(0.38066, 0.47583, 0.2855));
+  }
+
+  #[test]
+  fn test_render_is_independent_of_thread_count() {
+    let w = default_world();
+    let mut c = Camera::new(23, 17, PI / 2.0);
+    let from = Tuple::point(1.0, 0.5, -5.0);
+    let to = Tuple::point(0.0, 0.0, 0.0);
+    let up = Tuple::vector(0.0, 1.0, 0.0);
+    c.set_transform(Mat4::view_transform(from, to, up));
+
+    let single = c.render_with_threads(&w, 1);
+    for threads in [2, 3, 8, 64].iter() {
+      let image = c.render_with_threads(&w, *threads);
+      for y in 0..c.vsize() {
+        for x in 0..c.hsize() {
+          let (a, b) = (image[(x, y)], single[(x, y)]);
+          assert_eq!((a.red(), a.green(), a.blue()), (b.red(), b.green(), b.blue()));
+        }
+      }
+    }
+  }
+}
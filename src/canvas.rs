@@ -1,4 +1,5 @@
 use super::color::Color;
+use super::ppm::{PpmFormat, PpmOptions};
 use std::ops::{Index, IndexMut};
 
 #[derive(Debug)]
@@ -11,13 +12,13 @@ pub struct Canvas {
 impl Index<(usize, usize)> for Canvas {
   type Output = Color;
   fn index(&self, idx: (usize, usize)) -> &Color {
-    &self.cells[idx.1 * self.height + idx.0]
+    &self.cells[idx.1 * self.width + idx.0]
   }
 }
 
 impl IndexMut<(usize, usize)> for Canvas {
   fn index_mut(&mut self, idx: (usize, usize)) -> &mut Color {
-    &mut self.cells[idx.1 * self.height + idx.0]
+    &mut self.cells[idx.1 * self.width + idx.0]
   }
 }
 
@@ -31,31 +32,22 @@ impl Canvas {
     Canvas { height, width, cells }
   }
 
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
   pub fn is_in_bounds(&self, x: usize, y: usize) -> bool {
     x < self.width && y < self.height
   }
 
   pub fn to_ppm(&self) -> String {
-    let mut s = String::with_capacity(self.width * self.height * 12 + 64);
-    s.push_str("P3\n");
-    s.push_str(&self.width.to_string());
-    s.push_str(" ");
-    s.push_str(&self.height.to_string());
-    s.push_str("\n");
-    s.push_str("255\n");
-
-    for y in 0..self.height {
-      let mut pixels: Vec<String> = Vec::with_capacity(self.width);
-
-      for x in 0..self.width {
-        let pixel = self[(x, y)];
-        pixels.push(format!("{} {} {}", pixel.red_u8(), pixel.green_u8(), pixel.blue_u8()));
-      }
-
-      s.push_str(&format!("{}\n", &pixels.join(" ")));
-    }
-
-    s
+    let mut buf = Vec::with_capacity(self.width * self.height * 12 + 64);
+    self.write_ppm(&mut buf, &PpmOptions::new(PpmFormat::Plain)).expect("writing to a Vec can't fail");
+    String::from_utf8(buf).expect("plain PPM is ASCII")
   }
 }
 
@@ -85,6 +77,19 @@ mod tests {
     assert_eq!(canvas[(1, 0)], Color::new(0.0, 0.0, 1.0));
   }
 
+  #[test]
+  fn test_set_non_square() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas[(2, 0)] = Color::new(1.0, 0.0, 0.0);
+    canvas[(0, 1)] = Color::new(0.0, 1.0, 0.0);
+
+    assert_eq!(canvas.width(), 3);
+    assert_eq!(canvas.height(), 2);
+    assert_eq!(canvas[(2, 0)], Color::new(1.0, 0.0, 0.0));
+    assert_eq!(canvas[(0, 1)], Color::new(0.0, 1.0, 0.0));
+    assert_eq!(canvas[(1, 1)], Color::new(0.0, 0.0, 0.0));
+  }
+
   #[test]
   fn test_to_ppm() {
     let mut canvas = Canvas::new(5, 3);
@@ -107,16 +112,12 @@ mod tests {
     assert_eq!(last_char, '\n');
   }
 
-  /*
-  The book recommends having this test, but feh reads the image file just file and wrapping
-  the lines was very slow, so I got rid of it.
-
   #[test]
   fn test_to_ppm_long_lines() {
     let mut canvas = Canvas::new(10, 2);
     for x in 0..10 {
       for y in 0..2 {
-        canvas.set(x, y, Color::new(1.0, 0.8, 0.6));
+        canvas[(x, y)] = Color::new(1.0, 0.8, 0.6);
       }
     }
 
@@ -128,5 +129,4 @@ mod tests {
     assert_eq!(lines[5], "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
     assert_eq!(lines[6], "153 255 204 153 255 204 153 255 204 153 255 204 153");
   }
-  */
-}
\ No newline at end of file
+}
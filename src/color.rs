@@ -40,11 +40,11 @@ impl Color {
   fn to_u8(n: f32) -> u8 {
     let r = n * 255.0;
     if r > 255.0 {
-      return 255;
+      255
     } else if r <= 0.0 {
-      return 0;
+      0
     } else {
-      return r.round() as u8;
+      r.round() as u8
     }
   }
 }
@@ -0,0 +1,265 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// A double-napped cone around the y axis with its tips meeting at the origin.
+// The radius at any y is |y|. Truncation and caps work as for Cylinder.
+#[derive(Debug, Clone)]
+pub struct Cone {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  minimum: f32,
+  maximum: f32,
+  closed: bool,
+}
+
+impl Default for Cone {
+  fn default() -> Cone {
+    Cone::new()
+  }
+}
+
+impl Cone {
+  pub fn new() -> Cone {
+    Cone {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      minimum: f32::NEG_INFINITY,
+      maximum: f32::INFINITY,
+      closed: false,
+    }
+  }
+
+  pub fn minimum(&self) -> f32 {
+    self.minimum
+  }
+
+  pub fn maximum(&self) -> f32 {
+    self.maximum
+  }
+
+  pub fn closed(&self) -> bool {
+    self.closed
+  }
+
+  pub fn set_minimum(&mut self, minimum: f32) {
+    self.minimum = minimum;
+  }
+
+  pub fn set_maximum(&mut self, maximum: f32) {
+    self.maximum = maximum;
+  }
+
+  pub fn set_closed(&mut self, closed: bool) {
+    self.closed = closed;
+  }
+
+  fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+    if !self.closed || float::eq(ray.direction().y(), 0.0) {
+      return;
+    }
+
+    for y in [self.minimum, self.maximum].iter() {
+      let t = (y - ray.origin().y()) / ray.direction().y();
+      if check_cap(ray, t, y.abs()) {
+        xs.push(Intersection::new(t, self));
+      }
+    }
+  }
+}
+
+fn check_cap(ray: &Ray, t: f32, radius: f32) -> bool {
+  let x = ray.origin().x() + t * ray.direction().x();
+  let z = ray.origin().z() + t * ray.direction().z();
+  x.powi(2) + z.powi(2) <= radius.powi(2) + float::EPSILON
+}
+
+impl Shape for Cone {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    let mut xs = Vec::new();
+    let (o, d) = (ray.origin(), ray.direction());
+
+    let a = d.x().powi(2) - d.y().powi(2) + d.z().powi(2);
+    let b = 2.0 * o.x() * d.x() - 2.0 * o.y() * d.y() + 2.0 * o.z() * d.z();
+    let c = o.x().powi(2) - o.y().powi(2) + o.z().powi(2);
+
+    if float::eq(a, 0.0) {
+      // Parallel to one of the halves, so the ray crosses the other exactly
+      // once, unless it is also parallel to that and only the caps remain.
+      if !float::eq(b, 0.0) {
+        let t = -c / (2.0 * b);
+        let y = o.y() + t * d.y();
+        if self.minimum < y && y < self.maximum {
+          xs.push(Intersection::new(t, self));
+        }
+      }
+    } else {
+      // Grazing rays can land a rounding error below zero in f32.
+      let discriminant = b.powi(2) - 4.0 * a * c;
+      if discriminant < -float::EPSILON {
+        return xs;
+      }
+      let discriminant = discriminant.max(0.0);
+
+      let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+      let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+      if t0 > t1 {
+        std::mem::swap(&mut t0, &mut t1);
+      }
+
+      for t in [t0, t1].iter() {
+        let y = o.y() + t * d.y();
+        if self.minimum < y && y < self.maximum {
+          xs.push(Intersection::new(*t, self));
+        }
+      }
+    }
+
+    self.intersect_caps(ray, &mut xs);
+    xs
+  }
+
+  fn local_normal_at(&self, point: Tuple) -> Tuple {
+    let dist = point.x().powi(2) + point.z().powi(2);
+
+    if dist < self.maximum.powi(2) && point.y() >= self.maximum - float::EPSILON {
+      Tuple::vector(0.0, 1.0, 0.0)
+    } else if dist < self.minimum.powi(2) && point.y() <= self.minimum + float::EPSILON {
+      Tuple::vector(0.0, -1.0, 0.0)
+    } else {
+      let mut y = dist.sqrt();
+      if point.y() > 0.0 {
+        y = -y;
+      }
+      Tuple::vector(point.x(), y, point.z())
+    }
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    let limit = self.minimum.abs().max(self.maximum.abs());
+    BoundingBox::new(Tuple::point(-limit, self.minimum, -limit), Tuple::point(limit, self.maximum, limit))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_intersect() {
+    let shape = Cone::new();
+    let examples = [
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 5.0, 5.0),
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(1.0, 1.0, 1.0), 8.66025, 8.66025),
+      (Tuple::point(1.0, 1.0, -5.0), Tuple::vector(-0.5, -1.0, 1.0), 4.55006, 49.44994),
+    ];
+
+    for (origin, direction, t0, t1) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      let xs = shape.local_intersect(&r);
+      assert_eq!(xs.len(), 2);
+      assert!((xs[0].t() - t0).abs() < 0.001);
+      assert!((xs[1].t() - t1).abs() < 0.001);
+    }
+  }
+
+  #[test]
+  fn test_intersect_parallel_to_one_half() {
+    let shape = Cone::new();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -1.0), Tuple::vector(0.0, 1.0, 1.0).normalize());
+    let xs = shape.local_intersect(&r);
+
+    assert_eq!(xs.len(), 1);
+    assert!((xs[0].t() - 0.35355).abs() < 0.0001);
+  }
+
+  #[test]
+  fn test_intersect_caps() {
+    let mut shape = Cone::new();
+    shape.set_minimum(-0.5);
+    shape.set_maximum(0.5);
+    shape.set_closed(true);
+    let examples = [
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0), 0),
+      (Tuple::point(0.0, 0.0, -0.25), Tuple::vector(0.0, 1.0, 1.0), 2),
+      (Tuple::point(0.0, 0.0, -0.25), Tuple::vector(0.0, 1.0, 0.0), 4),
+    ];
+
+    for (origin, direction, count) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert_eq!(shape.local_intersect(&r).len(), *count);
+    }
+  }
+
+  #[test]
+  fn test_normal_at() {
+    let shape = Cone::new();
+    let examples = [
+      (Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 0.0)),
+      (Tuple::point(1.0, 1.0, 1.0), Tuple::vector(1.0, -(2f32.sqrt()), 1.0)),
+      (Tuple::point(-1.0, -1.0, 0.0), Tuple::vector(-1.0, 1.0, 0.0)),
+    ];
+
+    for (point, normal) in examples.iter() {
+      assert_eq!(shape.local_normal_at(*point), *normal);
+    }
+  }
+
+  #[test]
+  fn test_normal_at_caps() {
+    let mut shape = Cone::new();
+    shape.set_minimum(-1.0);
+    shape.set_maximum(2.0);
+    shape.set_closed(true);
+
+    assert_eq!(shape.local_normal_at(Tuple::point(0.5, 2.0, 0.5)), Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(shape.local_normal_at(Tuple::point(0.5, -1.0, 0.0)), Tuple::vector(0.0, -1.0, 0.0));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let b = Cone::new().bounds();
+    assert_eq!(b.min(), Tuple::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY));
+    assert_eq!(b.max(), Tuple::point(f32::INFINITY, f32::INFINITY, f32::INFINITY));
+
+    let mut shape = Cone::new();
+    shape.set_minimum(-5.0);
+    shape.set_maximum(3.0);
+    let b = shape.bounds();
+    assert_eq!(b.min(), Tuple::point(-5.0, -5.0, -5.0));
+    assert_eq!(b.max(), Tuple::point(5.0, 3.0, 5.0));
+  }
+}
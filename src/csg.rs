@@ -0,0 +1,295 @@
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{self, Shape, Parent};
+use super::intersection::{Intersection, Intersections};
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operation {
+  Union,
+  Intersection,
+  Difference,
+}
+
+impl Operation {
+  // Whether a hit on one side survives, given whether it is on the left shape
+  // and whether the ray is currently inside the left and right shapes.
+  pub fn allows(&self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+    match self {
+      Operation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+      Operation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+      Operation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+    }
+  }
+}
+
+// Constructive solid geometry: two shapes combined by an operation into one.
+// Like a group, the children are transformed along with it.
+#[derive(Debug)]
+pub struct Csg {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  operation: Operation,
+  left: Box<dyn Shape>,
+  right: Box<dyn Shape>,
+}
+
+impl Csg {
+  pub fn new(operation: Operation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Csg {
+    let mut csg = Csg {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      operation,
+      left,
+      right,
+    };
+    csg.reparent_children();
+    csg
+  }
+
+  pub fn operation(&self) -> Operation {
+    self.operation
+  }
+
+  pub fn left(&self) -> &dyn Shape {
+    self.left.as_ref()
+  }
+
+  pub fn right(&self) -> &dyn Shape {
+    self.right.as_ref()
+  }
+
+  // Walks the sorted intersections of both children, tracking whether the ray
+  // is inside each, and keeps those the operation allows.
+  pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Vec<Intersection<'a>> {
+    let mut in_left = false;
+    let mut in_right = false;
+    let mut result = Vec::new();
+
+    for i in xs {
+      let left_hit = self.left.includes(i.object());
+
+      if self.operation.allows(left_hit, in_left, in_right) {
+        result.push(i);
+      }
+
+      if left_hit {
+        in_left = !in_left;
+      } else {
+        in_right = !in_right;
+      }
+    }
+
+    result
+  }
+
+  fn reparent_children(&mut self) {
+    let parent = Arc::new(Parent::new(self.transform, self.parent.clone()));
+    self.left.set_parent(Some(parent.clone()));
+    self.right.set_parent(Some(parent));
+  }
+}
+
+impl Shape for Csg {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+    self.reparent_children();
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  // Passed on to both children, as a group does.
+  fn set_material(&mut self, material: Material) {
+    self.left.set_material(material.clone());
+    self.right.set_material(material.clone());
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+    self.reparent_children();
+  }
+
+  fn includes(&self, other: &dyn Shape) -> bool {
+    shape::same(self, other) || self.left.includes(other) || self.right.includes(other)
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    if !self.bounds().intersects(ray) {
+      return vec![];
+    }
+
+    let mut xs: Vec<Intersection<'_>> = self.left.intersect(ray).into_iter().collect();
+    xs.extend(self.right.intersect(ray));
+    self.filter_intersections(Intersections::new(xs))
+  }
+
+  fn local_normal_at(&self, _point: Tuple) -> Tuple {
+    // As with groups, intersections only ever point at the children.
+    panic!("CSG shapes have no normal of their own");
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    let mut bounds = self.left.parent_space_bounds();
+    bounds.add_box(&self.right.parent_space_bounds());
+    bounds
+  }
+
+  fn divide(&mut self, threshold: usize) {
+    self.left.divide(threshold);
+    self.right.divide(threshold);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+  use super::super::sphere::Sphere;
+  use super::super::cube::Cube;
+
+  #[test]
+  fn test_new() {
+    let csg = Csg::new(Operation::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+
+    assert_eq!(csg.operation(), Operation::Union);
+    assert!(csg.left().parent().is_some());
+    assert!(csg.right().parent().is_some());
+  }
+
+  #[test]
+  fn test_set_material_applies_to_children() {
+    let mut csg = Csg::new(Operation::Difference, Box::new(Sphere::new()), Box::new(Cube::new()));
+    let mut m = Material::new();
+    m.set_ambient(1.0);
+    csg.set_material(m.clone());
+
+    assert_eq!(*csg.material(), m);
+    assert_eq!(*csg.left().material(), m);
+    assert_eq!(*csg.right().material(), m);
+  }
+
+  #[test]
+  fn test_rules() {
+    let examples = [
+      (Operation::Union, true, true, true, false),
+      (Operation::Union, true, true, false, true),
+      (Operation::Union, true, false, true, false),
+      (Operation::Union, true, false, false, true),
+      (Operation::Union, false, true, true, false),
+      (Operation::Union, false, true, false, false),
+      (Operation::Union, false, false, true, true),
+      (Operation::Union, false, false, false, true),
+      (Operation::Intersection, true, true, true, true),
+      (Operation::Intersection, true, true, false, false),
+      (Operation::Intersection, true, false, true, true),
+      (Operation::Intersection, true, false, false, false),
+      (Operation::Intersection, false, true, true, true),
+      (Operation::Intersection, false, true, false, true),
+      (Operation::Intersection, false, false, true, false),
+      (Operation::Intersection, false, false, false, false),
+      (Operation::Difference, true, true, true, false),
+      (Operation::Difference, true, true, false, true),
+      (Operation::Difference, true, false, true, false),
+      (Operation::Difference, true, false, false, true),
+      (Operation::Difference, false, true, true, true),
+      (Operation::Difference, false, true, false, true),
+      (Operation::Difference, false, false, true, false),
+      (Operation::Difference, false, false, false, false),
+    ];
+
+    for (op, lhit, inl, inr, result) in examples.iter() {
+      assert_eq!(op.allows(*lhit, *inl, *inr), *result);
+    }
+  }
+
+  #[test]
+  fn test_filter_intersections() {
+    let examples = [
+      (Operation::Union, 0, 3),
+      (Operation::Intersection, 1, 2),
+      (Operation::Difference, 0, 1),
+    ];
+
+    for (op, x0, x1) in examples.iter() {
+      let csg = Csg::new(*op, Box::new(Sphere::new()), Box::new(Cube::new()));
+      let (s1, s2) = (csg.left(), csg.right());
+      let xs = Intersections::new(vec![
+        Intersection::new(1.0, s1),
+        Intersection::new(2.0, s2),
+        Intersection::new(3.0, s1),
+        Intersection::new(4.0, s2),
+      ]);
+      let result = csg.filter_intersections(xs.clone());
+
+      assert_eq!(result.len(), 2);
+      assert!(result[0] == xs[*x0]);
+      assert!(result[1] == xs[*x1]);
+    }
+  }
+
+  #[test]
+  fn test_intersect_miss() {
+    let csg = Csg::new(Operation::Union, Box::new(Sphere::new()), Box::new(Cube::new()));
+    let r = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(csg.local_intersect(&r).is_empty());
+  }
+
+  #[test]
+  fn test_intersect_hit() {
+    let mut s2 = Sphere::new();
+    s2.set_transform(Mat4::translation(0.0, 0.0, 0.5));
+    let csg = Csg::new(Operation::Union, Box::new(Sphere::new()), Box::new(s2));
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = csg.local_intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 4.0));
+    assert!(shape::same(xs[0].object(), csg.left()));
+    assert!(float::eq(xs[1].t(), 6.5));
+    assert!(shape::same(xs[1].object(), csg.right()));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let mut right = Sphere::new();
+    right.set_transform(Mat4::translation(2.0, 3.0, 4.0));
+    let csg = Csg::new(Operation::Difference, Box::new(Sphere::new()), Box::new(right));
+    let b = csg.bounds();
+
+    assert_eq!(b.min(), Tuple::point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), Tuple::point(3.0, 4.0, 5.0));
+  }
+
+  #[test]
+  fn test_difference_drilled_cube() {
+    // A cube with a thinner cube removed through its middle along z.
+    let mut drill = Cube::new();
+    drill.set_transform(Mat4::scaling(0.5, 0.5, 2.0));
+    let csg = Csg::new(Operation::Difference, Box::new(Cube::new()), Box::new(drill));
+
+    let through_hole = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(csg.local_intersect(&through_hole).is_empty());
+
+    let into_side = Ray::new(Tuple::point(-5.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0));
+    let xs = csg.local_intersect(&into_side);
+    let ts: Vec<f32> = xs.iter().map(|i| i.t()).collect();
+    assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+  }
+}
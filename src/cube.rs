@@ -0,0 +1,175 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// An axis-aligned cube spanning -1 to 1 on every axis.
+#[derive(Debug, Clone)]
+pub struct Cube {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+}
+
+impl Default for Cube {
+  fn default() -> Cube {
+    Cube::new()
+  }
+}
+
+impl Cube {
+  pub fn new() -> Cube {
+    Cube { transform: Transform::identity(), material: Material::new(), parent: None }
+  }
+}
+
+// Where the ray enters and leaves the slab between -1 and 1 on one axis.
+fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
+  let tmin_numerator = -1.0 - origin;
+  let tmax_numerator = 1.0 - origin;
+
+  let (tmin, tmax) = if direction.abs() >= float::EPSILON {
+    (tmin_numerator / direction, tmax_numerator / direction)
+  } else {
+    (tmin_numerator * f32::INFINITY, tmax_numerator * f32::INFINITY)
+  };
+
+  if tmin > tmax {
+    (tmax, tmin)
+  } else {
+    (tmin, tmax)
+  }
+}
+
+impl Shape for Cube {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    let (xtmin, xtmax) = check_axis(ray.origin().x(), ray.direction().x());
+    let (ytmin, ytmax) = check_axis(ray.origin().y(), ray.direction().y());
+    let (ztmin, ztmax) = check_axis(ray.origin().z(), ray.direction().z());
+
+    // The ray is inside the cube for the overlap of all three slabs.
+    let tmin = xtmin.max(ytmin).max(ztmin);
+    let tmax = xtmax.min(ytmax).min(ztmax);
+
+    if tmin > tmax {
+      return vec![];
+    }
+
+    vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+  }
+
+  fn local_normal_at(&self, point: Tuple) -> Tuple {
+    let maxc = point.x().abs().max(point.y().abs()).max(point.z().abs());
+
+    if maxc == point.x().abs() {
+      Tuple::vector(point.x(), 0.0, 0.0)
+    } else if maxc == point.y().abs() {
+      Tuple::vector(0.0, point.y(), 0.0)
+    } else {
+      Tuple::vector(0.0, 0.0, point.z())
+    }
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_intersect() {
+    let c = Cube::new();
+    let examples = [
+      (Tuple::point(5.0, 0.5, 0.0), Tuple::vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+      (Tuple::point(-5.0, 0.5, 0.0), Tuple::vector(1.0, 0.0, 0.0), 4.0, 6.0),
+      (Tuple::point(0.5, 5.0, 0.0), Tuple::vector(0.0, -1.0, 0.0), 4.0, 6.0),
+      (Tuple::point(0.5, -5.0, 0.0), Tuple::vector(0.0, 1.0, 0.0), 4.0, 6.0),
+      (Tuple::point(0.5, 0.0, 5.0), Tuple::vector(0.0, 0.0, -1.0), 4.0, 6.0),
+      (Tuple::point(0.5, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 4.0, 6.0),
+      (Tuple::point(0.0, 0.5, 0.0), Tuple::vector(0.0, 0.0, 1.0), -1.0, 1.0),
+    ];
+
+    for (origin, direction, t1, t2) in examples.iter() {
+      let xs = c.local_intersect(&Ray::new(*origin, *direction));
+      assert_eq!(xs.len(), 2);
+      assert!(float::eq(xs[0].t(), *t1));
+      assert!(float::eq(xs[1].t(), *t2));
+    }
+  }
+
+  #[test]
+  fn test_intersect_miss() {
+    let c = Cube::new();
+    let examples = [
+      (Tuple::point(-2.0, 0.0, 0.0), Tuple::vector(0.2673, 0.5345, 0.8018)),
+      (Tuple::point(0.0, -2.0, 0.0), Tuple::vector(0.8018, 0.2673, 0.5345)),
+      (Tuple::point(0.0, 0.0, -2.0), Tuple::vector(0.5345, 0.8018, 0.2673)),
+      (Tuple::point(2.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, -1.0)),
+      (Tuple::point(0.0, 2.0, 2.0), Tuple::vector(0.0, -1.0, 0.0)),
+      (Tuple::point(2.0, 2.0, 0.0), Tuple::vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (origin, direction) in examples.iter() {
+      assert!(c.local_intersect(&Ray::new(*origin, *direction)).is_empty());
+    }
+  }
+
+  #[test]
+  fn test_normal_at() {
+    let c = Cube::new();
+    let examples = [
+      (Tuple::point(1.0, 0.5, -0.8), Tuple::vector(1.0, 0.0, 0.0)),
+      (Tuple::point(-1.0, -0.2, 0.9), Tuple::vector(-1.0, 0.0, 0.0)),
+      (Tuple::point(-0.4, 1.0, -0.1), Tuple::vector(0.0, 1.0, 0.0)),
+      (Tuple::point(0.3, -1.0, -0.7), Tuple::vector(0.0, -1.0, 0.0)),
+      (Tuple::point(-0.6, 0.3, 1.0), Tuple::vector(0.0, 0.0, 1.0)),
+      (Tuple::point(0.4, 0.4, -1.0), Tuple::vector(0.0, 0.0, -1.0)),
+      (Tuple::point(1.0, 1.0, 1.0), Tuple::vector(1.0, 0.0, 0.0)),
+      (Tuple::point(-1.0, -1.0, -1.0), Tuple::vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (point, normal) in examples.iter() {
+      assert_eq!(c.local_normal_at(*point), *normal);
+    }
+  }
+
+  #[test]
+  fn test_bounds() {
+    let b = Cube::new().bounds();
+    assert_eq!(b.min(), Tuple::point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), Tuple::point(1.0, 1.0, 1.0));
+  }
+}
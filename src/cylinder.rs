@@ -0,0 +1,296 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// A cylinder of radius 1 around the y axis, infinitely long unless truncated
+// by minimum and maximum. The truncated ends are open unless closed is set.
+#[derive(Debug, Clone)]
+pub struct Cylinder {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  minimum: f32,
+  maximum: f32,
+  closed: bool,
+}
+
+impl Default for Cylinder {
+  fn default() -> Cylinder {
+    Cylinder::new()
+  }
+}
+
+impl Cylinder {
+  pub fn new() -> Cylinder {
+    Cylinder {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      minimum: f32::NEG_INFINITY,
+      maximum: f32::INFINITY,
+      closed: false,
+    }
+  }
+
+  pub fn minimum(&self) -> f32 {
+    self.minimum
+  }
+
+  pub fn maximum(&self) -> f32 {
+    self.maximum
+  }
+
+  pub fn closed(&self) -> bool {
+    self.closed
+  }
+
+  pub fn set_minimum(&mut self, minimum: f32) {
+    self.minimum = minimum;
+  }
+
+  pub fn set_maximum(&mut self, maximum: f32) {
+    self.maximum = maximum;
+  }
+
+  pub fn set_closed(&mut self, closed: bool) {
+    self.closed = closed;
+  }
+
+  fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+    // Caps only matter on closed cylinders the ray isn't parallel to.
+    if !self.closed || float::eq(ray.direction().y(), 0.0) {
+      return;
+    }
+
+    for y in [self.minimum, self.maximum].iter() {
+      let t = (y - ray.origin().y()) / ray.direction().y();
+      if check_cap(ray, t) {
+        xs.push(Intersection::new(t, self));
+      }
+    }
+  }
+}
+
+// Whether the ray at t is within the unit radius of the cylinder.
+fn check_cap(ray: &Ray, t: f32) -> bool {
+  let x = ray.origin().x() + t * ray.direction().x();
+  let z = ray.origin().z() + t * ray.direction().z();
+  x.powi(2) + z.powi(2) <= 1.0 + float::EPSILON
+}
+
+impl Shape for Cylinder {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    let mut xs = Vec::new();
+    let (o, d) = (ray.origin(), ray.direction());
+
+    // A ray parallel to the y axis can only hit the caps.
+    let a = d.x().powi(2) + d.z().powi(2);
+    if !float::eq(a, 0.0) {
+      let b = 2.0 * o.x() * d.x() + 2.0 * o.z() * d.z();
+      let c = o.x().powi(2) + o.z().powi(2) - 1.0;
+
+      let discriminant = b.powi(2) - 4.0 * a * c;
+      if discriminant < 0.0 {
+        return xs;
+      }
+
+      let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+      let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+      if t0 > t1 {
+        std::mem::swap(&mut t0, &mut t1);
+      }
+
+      for t in [t0, t1].iter() {
+        let y = o.y() + t * d.y();
+        if self.minimum < y && y < self.maximum {
+          xs.push(Intersection::new(*t, self));
+        }
+      }
+    }
+
+    self.intersect_caps(ray, &mut xs);
+    xs
+  }
+
+  fn local_normal_at(&self, point: Tuple) -> Tuple {
+    let dist = point.x().powi(2) + point.z().powi(2);
+
+    if dist < 1.0 && point.y() >= self.maximum - float::EPSILON {
+      Tuple::vector(0.0, 1.0, 0.0)
+    } else if dist < 1.0 && point.y() <= self.minimum + float::EPSILON {
+      Tuple::vector(0.0, -1.0, 0.0)
+    } else {
+      Tuple::vector(point.x(), 0.0, point.z())
+    }
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    BoundingBox::new(Tuple::point(-1.0, self.minimum, -1.0), Tuple::point(1.0, self.maximum, 1.0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_intersect_miss() {
+    let cyl = Cylinder::new();
+    let examples = [
+      (Tuple::point(1.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)),
+      (Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)),
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(1.0, 1.0, 1.0)),
+    ];
+
+    for (origin, direction) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert!(cyl.local_intersect(&r).is_empty());
+    }
+  }
+
+  #[test]
+  fn test_intersect_hit() {
+    let cyl = Cylinder::new();
+    let examples = [
+      (Tuple::point(1.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 5.0, 5.0),
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 4.0, 6.0),
+      (Tuple::point(0.5, 0.0, -5.0), Tuple::vector(0.1, 1.0, 1.0), 6.80798, 7.08872),
+    ];
+
+    for (origin, direction, t0, t1) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      let xs = cyl.local_intersect(&r);
+      assert_eq!(xs.len(), 2);
+      assert!((xs[0].t() - t0).abs() < 0.0001);
+      assert!((xs[1].t() - t1).abs() < 0.0001);
+    }
+  }
+
+  #[test]
+  fn test_normal_at() {
+    let cyl = Cylinder::new();
+    let examples = [
+      (Tuple::point(1.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0)),
+      (Tuple::point(0.0, 5.0, -1.0), Tuple::vector(0.0, 0.0, -1.0)),
+      (Tuple::point(0.0, -2.0, 1.0), Tuple::vector(0.0, 0.0, 1.0)),
+      (Tuple::point(-1.0, 1.0, 0.0), Tuple::vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (point, normal) in examples.iter() {
+      assert_eq!(cyl.local_normal_at(*point), *normal);
+    }
+  }
+
+  #[test]
+  fn test_default_bounds() {
+    let cyl = Cylinder::new();
+    assert_eq!(cyl.minimum(), f32::NEG_INFINITY);
+    assert_eq!(cyl.maximum(), f32::INFINITY);
+    assert!(!cyl.closed());
+  }
+
+  #[test]
+  fn test_intersect_truncated() {
+    let mut cyl = Cylinder::new();
+    cyl.set_minimum(1.0);
+    cyl.set_maximum(2.0);
+    let examples = [
+      (Tuple::point(0.0, 1.5, 0.0), Tuple::vector(0.1, 1.0, 0.0), 0),
+      (Tuple::point(0.0, 3.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 0),
+      (Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 0),
+      (Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 0),
+      (Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0), 0),
+      (Tuple::point(0.0, 1.5, -2.0), Tuple::vector(0.0, 0.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert_eq!(cyl.local_intersect(&r).len(), *count);
+    }
+  }
+
+  #[test]
+  fn test_intersect_caps() {
+    let mut cyl = Cylinder::new();
+    cyl.set_minimum(1.0);
+    cyl.set_maximum(2.0);
+    cyl.set_closed(true);
+    let examples = [
+      (Tuple::point(0.0, 3.0, 0.0), Tuple::vector(0.0, -1.0, 0.0), 2),
+      (Tuple::point(0.0, 3.0, -2.0), Tuple::vector(0.0, -1.0, 2.0), 2),
+      (Tuple::point(0.0, 4.0, -2.0), Tuple::vector(0.0, -1.0, 1.0), 2),
+      (Tuple::point(0.0, 0.0, -2.0), Tuple::vector(0.0, 1.0, 2.0), 2),
+      (Tuple::point(0.0, -1.0, -2.0), Tuple::vector(0.0, 1.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in examples.iter() {
+      let r = Ray::new(*origin, direction.normalize());
+      assert_eq!(cyl.local_intersect(&r).len(), *count);
+    }
+  }
+
+  #[test]
+  fn test_normal_at_caps() {
+    let mut cyl = Cylinder::new();
+    cyl.set_minimum(1.0);
+    cyl.set_maximum(2.0);
+    cyl.set_closed(true);
+    let examples = [
+      (Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0)),
+      (Tuple::point(0.5, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0)),
+      (Tuple::point(0.0, 1.0, 0.5), Tuple::vector(0.0, -1.0, 0.0)),
+      (Tuple::point(0.0, 2.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)),
+      (Tuple::point(0.5, 2.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)),
+      (Tuple::point(0.0, 2.0, 0.5), Tuple::vector(0.0, 1.0, 0.0)),
+    ];
+
+    for (point, normal) in examples.iter() {
+      assert_eq!(cyl.local_normal_at(*point), *normal);
+    }
+  }
+
+  #[test]
+  fn test_bounds() {
+    let b = Cylinder::new().bounds();
+    assert_eq!(b.min(), Tuple::point(-1.0, f32::NEG_INFINITY, -1.0));
+    assert_eq!(b.max(), Tuple::point(1.0, f32::INFINITY, 1.0));
+
+    let mut cyl = Cylinder::new();
+    cyl.set_minimum(-5.0);
+    cyl.set_maximum(3.0);
+    let b = cyl.bounds();
+    assert_eq!(b.min(), Tuple::point(-1.0, -5.0, -1.0));
+    assert_eq!(b.max(), Tuple::point(1.0, 3.0, 1.0));
+  }
+}
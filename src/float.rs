@@ -1,5 +1,11 @@
-const EPSILON: f32 = 0.00001;
+pub const EPSILON: f32 = 0.00001;
 
+// How far a hit point is nudged off its surface before casting secondary rays
+// from it. f32 intersection maths drifts by more than EPSILON, so this needs
+// some headroom or surfaces end up shadowing themselves.
+pub const OFFSET_EPSILON: f32 = EPSILON * 100.0;
+
+// Infinities are only equal to themselves; subtracting them would give NaN.
 pub fn eq(a: f32, b: f32) -> bool {
-  (a - b).abs() < EPSILON
-}
\ No newline at end of file
+  a == b || (a - b).abs() < EPSILON
+}
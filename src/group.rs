@@ -0,0 +1,427 @@
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{self, Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+type Children = Vec<Box<dyn Shape>>;
+
+// A collection of shapes that are transformed together. Each child holds the
+// chain of its ancestors' transforms, so changing a group's transform or
+// moving it into another group rebuilds that chain for everything below it.
+// The bounds of the children are kept up to date as they are added, since
+// they are checked on every intersection.
+#[derive(Debug)]
+pub struct Group {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  children: Children,
+  bounds: BoundingBox,
+}
+
+impl Default for Group {
+  fn default() -> Group {
+    Group::new()
+  }
+}
+
+impl Group {
+  pub fn new() -> Group {
+    Group {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      children: vec![],
+      bounds: BoundingBox::empty(),
+    }
+  }
+
+  pub fn children(&self) -> &[Box<dyn Shape>] {
+    &self.children
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.children.is_empty()
+  }
+
+  pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+    child.set_parent(Some(self.as_parent()));
+    self.bounds.add_box(&child.parent_space_bounds());
+    self.children.push(child);
+  }
+
+  // Sorts the children into those that fit entirely within either half of
+  // the group's bounds. Children that straddle the split are left behind.
+  pub fn partition_children(&mut self) -> (Children, Children) {
+    let (left_bounds, right_bounds) = self.bounds.split();
+    let mut left = vec![];
+    let mut right = vec![];
+    let mut remaining = vec![];
+
+    for child in self.children.drain(..) {
+      let bounds = child.parent_space_bounds();
+      if left_bounds.contains_box(&bounds) {
+        left.push(child);
+      } else if right_bounds.contains_box(&bounds) {
+        right.push(child);
+      } else {
+        remaining.push(child);
+      }
+    }
+
+    self.children = remaining;
+    (left, right)
+  }
+
+  pub fn make_subgroup(&mut self, children: Children) {
+    let mut subgroup = Group::new();
+    for child in children {
+      subgroup.add_child(child);
+    }
+    self.add_child(Box::new(subgroup));
+  }
+
+  fn as_parent(&self) -> Arc<Parent> {
+    Arc::new(Parent::new(self.transform, self.parent.clone()))
+  }
+
+  fn reparent_children(&mut self) {
+    let parent = self.as_parent();
+    for child in self.children.iter_mut() {
+      child.set_parent(Some(parent.clone()));
+    }
+  }
+}
+
+impl Shape for Group {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+    self.reparent_children();
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  // Passed on to the children the group has so far, so that a whole model
+  // can be given one material.
+  fn set_material(&mut self, material: Material) {
+    for child in self.children.iter_mut() {
+      child.set_material(material.clone());
+    }
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+    self.reparent_children();
+  }
+
+  fn includes(&self, other: &dyn Shape) -> bool {
+    shape::same(self, other) || self.children.iter().any(|child| child.includes(other))
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    if !self.bounds.intersects(ray) {
+      return vec![];
+    }
+
+    self.children.iter().flat_map(|child| child.intersect(ray)).collect()
+  }
+
+  fn local_normal_at(&self, _point: Tuple) -> Tuple {
+    // Intersections always point at a child, never at the group itself.
+    panic!("groups have no normal of their own");
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    self.bounds
+  }
+
+  // Recursively moves children into pairs of subgroups by splitting the
+  // bounds in half, stopping at groups with fewer than threshold children.
+  fn divide(&mut self, threshold: usize) {
+    if threshold <= self.children.len() {
+      let count = self.children.len();
+      let (left, right) = self.partition_children();
+      // Everything landing in one half means the split didn't separate
+      // anything, as when the children have no extent. A subgroup of them
+      // would have the same bounds and split the same way forever.
+      if left.len() == count {
+        self.children = left;
+      } else if right.len() == count {
+        self.children = right;
+      } else {
+        if !left.is_empty() {
+          self.make_subgroup(left);
+        }
+        if !right.is_empty() {
+          self.make_subgroup(right);
+        }
+      }
+    }
+
+    for child in self.children.iter_mut() {
+      child.divide(threshold);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::sphere::Sphere;
+  use super::super::cylinder::Cylinder;
+  use super::super::triangle::Triangle;
+  use super::super::intersection::Intersections;
+  use super::super::float;
+  use std::f32::consts::PI;
+
+  #[test]
+  fn test_new() {
+    let g = Group::new();
+    assert_eq!(*g.transform().matrix(), Mat4::identity());
+    assert!(g.is_empty());
+  }
+
+  #[test]
+  fn test_add_child() {
+    let mut g = Group::new();
+    g.add_child(Box::new(Sphere::new()));
+
+    assert_eq!(g.children().len(), 1);
+    assert!(g.children()[0].parent().is_some());
+  }
+
+  #[test]
+  fn test_includes_descendants() {
+    let mut inner = Group::new();
+    inner.add_child(Box::new(Sphere::new()));
+    let mut g = Group::new();
+    g.add_child(Box::new(inner));
+    let other = Sphere::new();
+
+    let nested = g.children()[0].as_ref();
+    assert!(g.includes(&g));
+    assert!(g.includes(nested));
+    assert!(!g.includes(&other));
+  }
+
+  #[test]
+  fn test_set_material_applies_to_children() {
+    let mut inner = Group::new();
+    inner.add_child(Box::new(Sphere::new()));
+    let mut g = Group::new();
+    g.add_child(Box::new(inner));
+    g.add_child(Box::new(Sphere::new()));
+
+    let mut m = Material::new();
+    m.set_ambient(1.0);
+    g.set_material(m.clone());
+
+    assert_eq!(*g.material(), m);
+    assert_eq!(*g.children()[0].material(), m);
+    assert_eq!(*g.children()[1].material(), m);
+  }
+
+  #[test]
+  fn test_intersect_empty() {
+    let g = Group::new();
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(g.local_intersect(&r).is_empty());
+  }
+
+  #[test]
+  fn test_intersect_children() {
+    let mut g = Group::new();
+    g.add_child(Box::new(Sphere::new()));
+    let mut s2 = Sphere::new();
+    s2.set_transform(Mat4::translation(0.0, 0.0, -3.0));
+    g.add_child(Box::new(s2));
+    let mut s3 = Sphere::new();
+    s3.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    g.add_child(Box::new(s3));
+
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = Intersections::new(g.local_intersect(&r));
+    let (s1, s2) = (g.children()[0].as_ref(), g.children()[1].as_ref());
+
+    assert_eq!(xs.len(), 4);
+    assert!(shape::same(xs[0].object(), s2));
+    assert!(shape::same(xs[1].object(), s2));
+    assert!(shape::same(xs[2].object(), s1));
+    assert!(shape::same(xs[3].object(), s1));
+  }
+
+  #[test]
+  fn test_intersect_transformed() {
+    let mut g = Group::new();
+    g.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    g.add_child(Box::new(s));
+
+    let r = Ray::new(Tuple::point(10.0, 0.0, -10.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert_eq!(g.intersect(&r).len(), 2);
+  }
+
+  #[test]
+  fn test_normal_at_nested_child() {
+    let mut g2 = Group::new();
+    g2.set_transform(Mat4::scaling(1.0, 2.0, 3.0));
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    g2.add_child(Box::new(s));
+
+    // Transforming the outer group after nesting still reaches the sphere.
+    let mut g1 = Group::new();
+    g1.add_child(Box::new(g2));
+    g1.set_transform(Mat4::rotation_y(PI / 2.0));
+
+    // The sphere ends up centred on (0, 0, -5) with its unit x radius along z.
+    let r = Ray::new(Tuple::point(0.0, 0.0, -10.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = g1.intersect(&r);
+    let hit = xs.hit().unwrap();
+    let n = hit.object().normal_at(r.position_at_time(hit.t()));
+
+    assert!(float::eq(hit.t(), 4.0));
+    assert_eq!(n, Tuple::vector(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(2.0, 5.0, -3.0) * Mat4::scaling(2.0, 2.0, 2.0));
+    let mut c = Cylinder::new();
+    c.set_minimum(-2.0);
+    c.set_maximum(2.0);
+    c.set_transform(Mat4::translation(-4.0, -1.0, 4.0) * Mat4::scaling(0.5, 1.0, 0.5));
+    let mut g = Group::new();
+    g.add_child(Box::new(s));
+    g.add_child(Box::new(c));
+    let b = g.bounds();
+
+    assert_eq!(b.min(), Tuple::point(-4.5, -3.0, -5.0));
+    assert_eq!(b.max(), Tuple::point(4.0, 7.0, 4.5));
+  }
+
+  #[test]
+  fn test_intersect_misses_bounds() {
+    let mut g = Group::new();
+    g.add_child(Box::new(Sphere::new()));
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+    assert!(g.local_intersect(&r).is_empty());
+  }
+
+  fn sphere_at(x: f32, y: f32, z: f32) -> Box<dyn Shape> {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(x, y, z));
+    Box::new(s)
+  }
+
+  #[test]
+  fn test_partition_children() {
+    let mut g = Group::new();
+    g.add_child(sphere_at(-2.0, 0.0, 0.0));
+    g.add_child(sphere_at(2.0, 0.0, 0.0));
+    g.add_child(Box::new(Sphere::new()));
+    let (left, right) = g.partition_children();
+
+    assert_eq!(g.children().len(), 1);
+    assert_eq!(g.children()[0].parent_space_bounds().min(), Tuple::point(-1.0, -1.0, -1.0));
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].parent_space_bounds().min(), Tuple::point(-3.0, -1.0, -1.0));
+    assert_eq!(right.len(), 1);
+    assert_eq!(right[0].parent_space_bounds().min(), Tuple::point(1.0, -1.0, -1.0));
+  }
+
+  #[test]
+  fn test_make_subgroup() {
+    let mut g = Group::new();
+    g.make_subgroup(vec![Box::new(Sphere::new()), Box::new(Sphere::new())]);
+
+    assert_eq!(g.children().len(), 1);
+    assert_eq!(g.bounds().max(), Tuple::point(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_divide() {
+    let mut big = Sphere::new();
+    big.set_transform(Mat4::scaling(4.0, 4.0, 4.0));
+    let mut g = Group::new();
+    g.add_child(sphere_at(-2.0, -2.0, 0.0));
+    g.add_child(sphere_at(-2.0, 2.0, 0.0));
+    g.add_child(Box::new(big));
+    g.divide(1);
+
+    // The big sphere straddles every split and stays put; the other two end
+    // up in a subgroup of their own.
+    assert_eq!(g.children().len(), 2);
+    assert_eq!(g.children()[0].parent_space_bounds().max(), Tuple::point(4.0, 4.0, 4.0));
+    let sub = g.children()[1].bounds();
+    assert_eq!(sub.min(), Tuple::point(-3.0, -3.0, -1.0));
+    assert_eq!(sub.max(), Tuple::point(-1.0, 3.0, 1.0));
+  }
+
+  #[test]
+  fn test_divide_below_threshold() {
+    let mut g = Group::new();
+    g.add_child(sphere_at(-2.0, 0.0, 0.0));
+    g.add_child(sphere_at(2.0, 0.0, 0.0));
+    g.divide(3);
+
+    assert_eq!(g.children().len(), 2);
+  }
+
+  #[test]
+  fn test_divide_degenerate_children() {
+    // Triangles squashed to a point fit in the same half of every split.
+    let p = Tuple::point(1.0, 2.0, 3.0);
+    let mut g = Group::new();
+    g.add_child(Box::new(Triangle::new(p, p, p)));
+    g.add_child(Box::new(Triangle::new(p, p, p)));
+    g.divide(1);
+
+    assert_eq!(g.children().len(), 2);
+    assert_eq!(g.bounds(), BoundingBox::new(p, p));
+
+    // Next to another shape they still get a subgroup, which then stops.
+    let mut g = Group::new();
+    g.add_child(Box::new(Triangle::new(p, p, p)));
+    g.add_child(Box::new(Triangle::new(p, p, p)));
+    g.add_child(sphere_at(-5.0, 0.0, 0.0));
+    g.divide(1);
+
+    assert_eq!(g.children().len(), 2);
+    assert_eq!(g.children()[1].bounds(), BoundingBox::new(p, p));
+  }
+
+  #[test]
+  fn test_divide_keeps_intersections() {
+    let mut g = Group::new();
+    for i in 0..8 {
+      g.add_child(sphere_at(i as f32 * 3.0, 0.0, 0.0));
+    }
+    g.set_transform(Mat4::scaling(0.5, 0.5, 0.5));
+    let r = Ray::new(Tuple::point(-5.0, 0.0, 0.0), Tuple::vector(1.0, 0.0, 0.0));
+    let before: Vec<f32> = g.intersect(&r).iter().map(|i| i.t()).collect();
+    g.divide(2);
+    let after: Vec<f32> = g.intersect(&r).iter().map(|i| i.t()).collect();
+
+    assert_eq!(before.len(), 16);
+    assert_eq!(before, after);
+  }
+}
@@ -0,0 +1,484 @@
+use super::color::Color;
+use super::canvas::Canvas;
+use std::fmt;
+use std::io::{self, Write};
+
+// Radiance RGBE stores each pixel as three 8-bit mantissas sharing one
+// exponent byte, so channels keep about two decimal digits of precision
+// relative to the brightest one, over a huge range. Negative values can't be
+// stored and are written as zero.
+
+// Scanlines this wide use per-channel run-length encoding, narrower ones are
+// written flat.
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+const MAX_RUN: usize = 127;
+const MAX_DUMP: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HdrError {
+  // The file doesn't start with #?, or isn't in RGBE format.
+  UnsupportedFormat,
+  // The header has no resolution line, or one that can't be parsed.
+  InvalidResolution { offset: usize },
+  // Only the standard -Y height +X width layout is supported.
+  UnsupportedOrientation,
+  ZeroSize,
+  // A run-length encoded scanline that runs past the end of its row.
+  InvalidRun { offset: usize },
+  UnexpectedEof,
+}
+
+impl fmt::Display for HdrError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      HdrError::UnsupportedFormat => write!(f, "not a Radiance RGBE file"),
+      HdrError::InvalidResolution { offset } => write!(f, "expected a resolution line at byte {}", offset),
+      HdrError::UnsupportedOrientation => write!(f, "only -Y height +X width images are supported"),
+      HdrError::ZeroSize => write!(f, "image has no pixels"),
+      HdrError::InvalidRun { offset } => write!(f, "run at byte {} goes past the end of the scanline", offset),
+      HdrError::UnexpectedEof => write!(f, "file ends before the image does"),
+    }
+  }
+}
+
+impl std::error::Error for HdrError {}
+
+fn to_rgbe(color: Color) -> [u8; 4] {
+  let r = color.red().max(0.0);
+  let g = color.green().max(0.0);
+  let b = color.blue().max(0.0);
+  let v = r.max(g).max(b);
+
+  // Anything this small rounds to zero anyway, and every float above it is
+  // normal, so the exponent can be read straight from the bits. NaN also
+  // ends up here.
+  if v.is_nan() || v < 1e-32 {
+    return [0, 0, 0, 0];
+  }
+
+  // v = m * 2^e with m in [0.5, 1). Anything too bright for the exponent
+  // byte, including infinity, saturates.
+  let e = ((v.to_bits() >> 23) & 0xff) as i32 - 126;
+  if e > 127 {
+    return [255, 255, 255, 255];
+  }
+  let scale = 256.0 / 2f32.powi(e);
+  [
+    (r * scale).min(255.0) as u8,
+    (g * scale).min(255.0) as u8,
+    (b * scale).min(255.0) as u8,
+    (e + 128) as u8,
+  ]
+}
+
+// Whether a scanline starting with these bytes uses the per-channel
+// run-length encoding rather than flat pixels.
+fn starts_rle(bytes: &[u8], width: usize) -> bool {
+  bytes.len() >= 4 && bytes[0] == 2 && bytes[1] == 2 && bytes[2] & 0x80 == 0
+    && (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+}
+
+// The fewest bytes a scanline can take, for a file whose first scanline
+// starts with these bytes. Run-length encoding needs a count and a value for
+// every 127 pixels of each channel. Old-style runs repeat the previous pixel
+// for a count spread over one marker per byte, so they can cover a whole
+// scanline in very little.
+fn min_scanline_len(first: &[u8], width: usize) -> usize {
+  if starts_rle(first, width) {
+    return 4 + 4 * 2 * width.div_ceil(MAX_RUN);
+  }
+  let mut markers = 1;
+  let mut longest = 255usize;
+  while longest < width {
+    markers += 1;
+    longest = longest.saturating_mul(256) | 255;
+  }
+  4 * markers
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+  if rgbe[3] == 0 {
+    return Color::new(0.0, 0.0, 0.0);
+  }
+  let f = 2f32.powi(rgbe[3] as i32 - (128 + 8));
+  Color::new(rgbe[0] as f32 * f, rgbe[1] as f32 * f, rgbe[2] as f32 * f)
+}
+
+// Runs of four or more equal bytes become a count and a byte, everything in
+// between is dumped as a count followed by the bytes themselves.
+fn encode_channel(data: &[u8], out: &mut Vec<u8>) {
+  let mut i = 0;
+  while i < data.len() {
+    let mut run_start = i;
+    let mut run_length = 0;
+    while run_start < data.len() {
+      run_length = data[run_start..].iter()
+        .take(MAX_RUN)
+        .take_while(|b| **b == data[run_start])
+        .count();
+      if run_length >= 4 {
+        break;
+      }
+      run_start += run_length;
+      run_length = 0;
+    }
+
+    while i < run_start {
+      let n = (run_start - i).min(MAX_DUMP);
+      out.push(n as u8);
+      out.extend_from_slice(&data[i..i + n]);
+      i += n;
+    }
+
+    if run_length > 0 {
+      out.push(128 + run_length as u8);
+      out.push(data[run_start]);
+      i += run_length;
+    }
+  }
+}
+
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn line(&mut self) -> Option<&'a [u8]> {
+    if self.pos >= self.data.len() {
+      return None;
+    }
+    let start = self.pos;
+    let end = self.data[start..].iter().position(|c| *c == b'\n')
+      .map_or(self.data.len(), |n| start + n);
+    self.pos = (end + 1).min(self.data.len());
+    Some(&self.data[start..end])
+  }
+
+  fn bytes(&mut self, n: usize) -> Result<&'a [u8], HdrError> {
+    let bytes = self.data.get(self.pos..self.pos + n).ok_or(HdrError::UnexpectedEof)?;
+    self.pos += n;
+    Ok(bytes)
+  }
+
+  fn scanline(&mut self, width: usize, row: &mut [[u8; 4]], previous: Option<[u8; 4]>) -> Result<(), HdrError> {
+    let first = self.bytes(4)?;
+
+    if starts_rle(first, width) {
+      if (first[2] as usize) << 8 | first[3] as usize != width {
+        return Err(HdrError::InvalidRun { offset: self.pos - 2 });
+      }
+      for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+          let offset = self.pos;
+          let count = self.bytes(1)?[0] as usize;
+          if count > 128 {
+            let count = count - 128;
+            let value = self.bytes(1)?[0];
+            if x + count > width {
+              return Err(HdrError::InvalidRun { offset });
+            }
+            for pixel in row[x..x + count].iter_mut() {
+              pixel[channel] = value;
+            }
+            x += count;
+          } else {
+            if count == 0 || x + count > width {
+              return Err(HdrError::InvalidRun { offset });
+            }
+            for (pixel, value) in row[x..x + count].iter_mut().zip(self.bytes(count)?) {
+              pixel[channel] = *value;
+            }
+            x += count;
+          }
+        }
+      }
+      return Ok(());
+    }
+
+    // Flat pixels, which older files may interleave with runs of the
+    // previous pixel marked by 1, 1, 1 and a count.
+    self.pos -= 4;
+    let mut x = 0;
+    let mut previous = previous;
+    let mut shift = 0;
+    while x < width {
+      let offset = self.pos;
+      let bytes = self.bytes(4)?;
+      let pixel = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+      if pixel[..3] == [1, 1, 1] {
+        let repeat = previous.ok_or(HdrError::InvalidRun { offset })?;
+        // Consecutive markers make up one count, a byte at a time, so a
+        // long enough chain of them can't fit in any scanline.
+        let count = match (pixel[3] as usize).checked_shl(shift) {
+          Some(count) if count >> shift == pixel[3] as usize && count <= width - x => count,
+          _ => return Err(HdrError::InvalidRun { offset }),
+        };
+        for p in row[x..x + count].iter_mut() {
+          *p = repeat;
+        }
+        x += count;
+        shift += 8;
+      } else {
+        row[x] = pixel;
+        previous = Some(pixel);
+        x += 1;
+        shift = 0;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Canvas {
+  pub fn write_hdr<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", self.height(), self.width())?;
+
+    let width = self.width();
+    let rle = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width);
+    let mut channels = vec![vec![0u8; width]; 4];
+    let mut row = Vec::with_capacity(width * 4 + 4);
+
+    for y in 0..self.height() {
+      row.clear();
+
+      if rle {
+        for x in 0..width {
+          let rgbe = to_rgbe(self[(x, y)]);
+          for (channel, value) in channels.iter_mut().zip(rgbe.iter()) {
+            channel[x] = *value;
+          }
+        }
+        row.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+        for channel in channels.iter() {
+          encode_channel(channel, &mut row);
+        }
+      } else {
+        for x in 0..width {
+          row.extend_from_slice(&to_rgbe(self[(x, y)]));
+        }
+      }
+
+      writer.write_all(&row)?;
+    }
+
+    writer.flush()
+  }
+
+  // Reads flat and run-length encoded RGBE files. Header variables other
+  // than FORMAT, such as EXPOSURE, are ignored.
+  pub fn from_hdr(data: &[u8]) -> Result<Canvas, HdrError> {
+    let mut reader = Reader { data, pos: 0 };
+
+    match reader.line() {
+      Some(line) if line.starts_with(b"#?") => (),
+      _ => return Err(HdrError::UnsupportedFormat),
+    }
+
+    loop {
+      match reader.line() {
+        None => return Err(HdrError::UnexpectedEof),
+        Some(b"") => break,
+        Some(line) if line.starts_with(b"FORMAT=") && line != b"FORMAT=32-bit_rle_rgbe" =>
+          return Err(HdrError::UnsupportedFormat),
+        Some(_) => (),
+      }
+    }
+
+    let offset = reader.pos;
+    let resolution = reader.line().ok_or(HdrError::UnexpectedEof)?;
+    let fields: Vec<&str> = std::str::from_utf8(resolution)
+      .map_err(|_| HdrError::InvalidResolution { offset })?
+      .split_whitespace()
+      .collect();
+    if fields.len() != 4 {
+      return Err(HdrError::InvalidResolution { offset });
+    }
+    if fields[0] != "-Y" || fields[2] != "+X" {
+      let axes = ["-Y", "+Y", "-X", "+X"];
+      return Err(if axes.contains(&fields[0]) && axes.contains(&fields[2]) {
+        HdrError::UnsupportedOrientation
+      } else {
+        HdrError::InvalidResolution { offset }
+      });
+    }
+    let height: usize = fields[1].parse().map_err(|_| HdrError::InvalidResolution { offset })?;
+    let width: usize = fields[3].parse().map_err(|_| HdrError::InvalidResolution { offset })?;
+    if width == 0 || height == 0 {
+      return Err(HdrError::ZeroSize);
+    }
+
+    // Check the data could possibly hold that many scanlines before
+    // allocating them. A file is taken to encode every scanline the way it
+    // does the first.
+    let remaining = &data[reader.pos..];
+    if width.checked_mul(height).is_none() || height > remaining.len() / min_scanline_len(remaining, width) {
+      return Err(HdrError::UnexpectedEof);
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let mut row = vec![[0u8; 4]; width];
+    for y in 0..height {
+      let previous = if y > 0 { Some(row[width - 1]) } else { None };
+      reader.scanline(width, &mut row, previous)?;
+      for (x, rgbe) in row.iter().enumerate() {
+        canvas[(x, y)] = from_rgbe(*rgbe);
+      }
+    }
+
+    Ok(canvas)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // RGBE keeps 8 bits relative to the brightest channel of each pixel.
+  fn close(a: Color, b: Color) -> bool {
+    let tolerance = a.red().max(a.green()).max(a.blue()) / 128.0;
+    (a.red() - b.red()).abs() <= tolerance
+      && (a.green() - b.green()).abs() <= tolerance
+      && (a.blue() - b.blue()).abs() <= tolerance
+  }
+
+  #[test]
+  fn test_rgbe() {
+    assert_eq!(to_rgbe(Color::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+    assert_eq!(from_rgbe([128, 64, 0, 129]), Color::new(1.0, 0.5, 0.0));
+    assert_eq!(to_rgbe(Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+    assert_eq!(to_rgbe(Color::new(-2.0, 1e-40, f32::NAN)), [0, 0, 0, 0]);
+    assert_eq!(from_rgbe([0, 0, 0, 0]), Color::new(0.0, 0.0, 0.0));
+    assert_eq!(to_rgbe(Color::new(f32::INFINITY, 1.0, 1.0)), [255, 255, 255, 255]);
+
+    for color in [Color::new(1000.0, 3.0, 0.25), Color::new(0.001, 0.002, 0.003), Color::new(65504.0, 1.0, 1e6)].iter() {
+      assert!(close(*color, from_rgbe(to_rgbe(*color))));
+    }
+  }
+
+  #[test]
+  fn test_encode_channel() {
+    let mut out = vec![];
+    encode_channel(&[1, 2, 3, 3, 3, 3, 3, 4, 4, 5], &mut out);
+    assert_eq!(out, vec![2, 1, 2, 133, 3, 3, 4, 4, 5]);
+
+    let mut out = vec![];
+    encode_channel(&[9; 300], &mut out);
+    assert_eq!(out, vec![255, 9, 255, 9, 128 + 46, 9]);
+  }
+
+  #[test]
+  fn test_write_hdr_header() {
+    let canvas = Canvas::new(3, 2);
+    let mut buf = Vec::new();
+    canvas.write_hdr(&mut buf).unwrap();
+
+    let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n";
+    assert_eq!(&buf[..header.len()], header);
+    assert_eq!(buf.len(), header.len() + 3 * 2 * 4);
+  }
+
+  #[test]
+  fn test_round_trip() {
+    for width in [3, 8, 40, 300].iter() {
+      let mut canvas = Canvas::new(*width, 3);
+      for x in 0..*width {
+        canvas[(x, 0)] = Color::new(x as f32 * 10.0, 0.5, 0.0);
+        canvas[(x, 1)] = Color::new(4.0, 4.0, 4.0);
+        canvas[(x, 2)] = Color::new((x % 3) as f32, 1e-3, 250.0);
+      }
+
+      let mut buf = Vec::new();
+      canvas.write_hdr(&mut buf).unwrap();
+      let read = Canvas::from_hdr(&buf).unwrap();
+
+      assert_eq!(read.width(), *width);
+      assert_eq!(read.height(), 3);
+      for y in 0..3 {
+        for x in 0..*width {
+          assert!(close(canvas[(x, y)], read[(x, y)]), "{:?} {:?}", canvas[(x, y)], read[(x, y)]);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_read_old_style_runs() {
+    let mut data = b"#?RGBE\n\n-Y 1 +X 5\n".to_vec();
+    data.extend_from_slice(&[128, 0, 0, 129, 1, 1, 1, 3, 0, 128, 0, 129]);
+
+    let canvas = Canvas::from_hdr(&data).unwrap();
+    for x in 0..4 {
+      assert_eq!(canvas[(x, 0)], Color::new(1.0, 0.0, 0.0));
+    }
+    assert_eq!(canvas[(4, 0)], Color::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_read_wide_old_style_runs() {
+    // Each scanline is 1000 pixels of red in two markers, far denser than
+    // run-length encoding could manage.
+    let mut data = b"#?RGBE\n\n-Y 4 +X 1000\n".to_vec();
+    data.extend_from_slice(&[128, 0, 0, 129, 1, 1, 1, 231, 1, 1, 1, 3]);
+    for _ in 0..3 {
+      data.extend_from_slice(&[1, 1, 1, 232, 1, 1, 1, 3]);
+    }
+
+    let canvas = Canvas::from_hdr(&data).unwrap();
+    assert_eq!(canvas.width(), 1000);
+    assert_eq!(canvas.height(), 4);
+    assert_eq!(canvas[(999, 3)], Color::new(1.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_long_chains_of_old_style_runs() {
+    let mut data = b"#?RGBE\n\n-Y 1 +X 5\n".to_vec();
+    data.extend_from_slice(&[128, 0, 0, 129]);
+    for _ in 0..9 {
+      data.extend_from_slice(&[1, 1, 1, 0]);
+    }
+    assert_eq!(Canvas::from_hdr(&data).unwrap_err(), HdrError::InvalidRun { offset: 54 });
+
+    // The second marker makes a count of 256 more pixels.
+    data.truncate(22);
+    data.extend_from_slice(&[1, 1, 1, 1, 1, 1, 1, 1]);
+    assert_eq!(Canvas::from_hdr(&data).unwrap_err(), HdrError::InvalidRun { offset: 26 });
+  }
+
+  #[test]
+  fn test_huge_headers() {
+    let examples: [&[u8]; 5] = [
+      b"#?RADIANCE\n\n-Y 4294967295 +X 4294967295\n",
+      b"#?RADIANCE\n\n-Y 60000 +X 60000\n\0\0\0\0",
+      b"#?RADIANCE\n\n-Y 3 +X 1\n\0\0\0\0\0\0\0\0",
+      // Two markers can't count past 65535 pixels.
+      b"#?RADIANCE\n\n-Y 2 +X 65536\n\0\0\0\0\x01\x01\x01\xff\x01\x01\x01\xff\0\0\0\0",
+      // Run-length encoding needs 2076 bytes for a scanline this wide.
+      b"#?RADIANCE\n\n-Y 2 +X 32767\n\x02\x02\x7f\xff\x80\0",
+    ];
+    for data in examples.iter() {
+      assert_eq!(Canvas::from_hdr(data).unwrap_err(), HdrError::UnexpectedEof);
+    }
+  }
+
+  #[test]
+  fn test_errors() {
+    let examples: [(&[u8], HdrError); 8] = [
+      (b"P6\n", HdrError::UnsupportedFormat),
+      (b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n", HdrError::UnsupportedFormat),
+      (b"#?RADIANCE\n", HdrError::UnexpectedEof),
+      (b"#?RADIANCE\n\n-Y 1 X 1\n", HdrError::InvalidResolution { offset: 12 }),
+      (b"#?RADIANCE\n\n+Y 1 +X 1\n", HdrError::UnsupportedOrientation),
+      (b"#?RADIANCE\n\n-Y 0 +X 1\n", HdrError::ZeroSize),
+      (b"#?RADIANCE\n\n-Y 1 +X 2\n\x80\x00\x00\x81", HdrError::UnexpectedEof),
+      (b"#?RADIANCE\n\n-Y 1 +X 8\n\x02\x02\x00\x08\x89\x00\0\0\0\0\0\0", HdrError::InvalidRun { offset: 26 }),
+    ];
+
+    for (data, expected) in examples.iter() {
+      assert_eq!(Canvas::from_hdr(data).unwrap_err(), *expected);
+    }
+  }
+}
@@ -0,0 +1,464 @@
+use super::float;
+use super::shape::{self, Shape};
+use super::tuple::Tuple;
+use super::ray::Ray;
+use std::ops::Index;
+use std::slice;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Intersection<'a> {
+  t: f32,
+  object: &'a dyn Shape,
+  u: f32,
+  v: f32,
+}
+
+impl<'a> Intersection<'a> {
+  pub fn new(t: f32, object: &'a dyn Shape) -> Intersection<'a> {
+    Intersection { t, object, u: 0.0, v: 0.0 }
+  }
+
+  // u and v locate the hit on a triangle relative to its corners, for shapes
+  // that interpolate across their surface.
+  pub fn with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Intersection<'a> {
+    Intersection { t, object, u, v }
+  }
+
+  pub fn t(&self) -> f32 {
+    self.t
+  }
+
+  pub fn object(&self) -> &'a dyn Shape {
+    self.object
+  }
+
+  pub fn u(&self) -> f32 {
+    self.u
+  }
+
+  pub fn v(&self) -> f32 {
+    self.v
+  }
+
+  // The full, sorted list of intersections along the ray is needed to work
+  // out which materials the hit sits between when refracting.
+  pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections<'a>) -> Computations<'a> {
+    let point = ray.position_at_time(self.t);
+    let eyev = -ray.direction();
+    let mut normalv = self.object.normal_at_hit(point, self);
+
+    let inside = normalv.dot(eyev) < 0.0;
+    if inside {
+      normalv = -normalv;
+    }
+
+    let over_point = point + normalv * float::OFFSET_EPSILON;
+    let under_point = point - normalv * float::OFFSET_EPSILON;
+    let reflectv = ray.direction().reflect(normalv);
+    let (n1, n2) = self.refractive_indices(xs);
+
+    Computations {
+      t: self.t,
+      object: self.object,
+      point,
+      over_point,
+      under_point,
+      eyev,
+      normalv,
+      reflectv,
+      inside,
+      n1,
+      n2,
+    }
+  }
+
+  // Walks the intersections up to this one, keeping track of which objects the
+  // ray is currently inside, to find the refractive index on either side.
+  fn refractive_indices(&self, xs: &Intersections<'a>) -> (f32, f32) {
+    let mut containers: Vec<&dyn Shape> = Vec::new();
+    let mut n1 = 1.0;
+
+    for i in xs.iter() {
+      if i == self {
+        n1 = containers.last().map_or(1.0, |o| o.material().refractive_index());
+      }
+
+      match containers.iter().position(|o| shape::same(*o, i.object)) {
+        Some(idx) => { containers.remove(idx); },
+        None => containers.push(i.object),
+      }
+
+      if i == self {
+        let n2 = containers.last().map_or(1.0, |o| o.material().refractive_index());
+        return (n1, n2);
+      }
+    }
+
+    (n1, 1.0)
+  }
+}
+
+// Precomputed state about an intersection that shading needs.
+#[derive(Debug, Copy, Clone)]
+pub struct Computations<'a> {
+  t: f32,
+  object: &'a dyn Shape,
+  point: Tuple,
+  over_point: Tuple,
+  under_point: Tuple,
+  eyev: Tuple,
+  normalv: Tuple,
+  reflectv: Tuple,
+  inside: bool,
+  n1: f32,
+  n2: f32,
+}
+
+impl<'a> Computations<'a> {
+  pub fn t(&self) -> f32 {
+    self.t
+  }
+
+  pub fn object(&self) -> &'a dyn Shape {
+    self.object
+  }
+
+  pub fn point(&self) -> Tuple {
+    self.point
+  }
+
+  pub fn over_point(&self) -> Tuple {
+    self.over_point
+  }
+
+  pub fn under_point(&self) -> Tuple {
+    self.under_point
+  }
+
+  pub fn eyev(&self) -> Tuple {
+    self.eyev
+  }
+
+  pub fn normalv(&self) -> Tuple {
+    self.normalv
+  }
+
+  pub fn reflectv(&self) -> Tuple {
+    self.reflectv
+  }
+
+  pub fn inside(&self) -> bool {
+    self.inside
+  }
+
+  pub fn n1(&self) -> f32 {
+    self.n1
+  }
+
+  pub fn n2(&self) -> f32 {
+    self.n2
+  }
+
+  // Schlick's approximation of the Fresnel effect: the fraction of light that
+  // is reflected rather than refracted at this hit.
+  pub fn schlick(&self) -> f32 {
+    let mut cos = self.eyev.dot(self.normalv);
+
+    if self.n1 > self.n2 {
+      let n = self.n1 / self.n2;
+      let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+      if sin2_t > 1.0 {
+        return 1.0;
+      }
+
+      cos = (1.0 - sin2_t).sqrt();
+    }
+
+    let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+  }
+}
+
+impl<'a> PartialEq for Intersection<'a> {
+  fn eq(&self, other: &Intersection<'a>) -> bool {
+    float::eq(self.t, other.t) && shape::same(self.object, other.object)
+  }
+}
+
+// Always kept sorted by t, so the hit is the first non-negative entry.
+#[derive(Debug, Clone)]
+pub struct Intersections<'a> {
+  intersections: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+  pub fn new(mut intersections: Vec<Intersection<'a>>) -> Intersections<'a> {
+    intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+    Intersections { intersections }
+  }
+
+  pub fn len(&self) -> usize {
+    self.intersections.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.intersections.is_empty()
+  }
+
+  pub fn iter(&self) -> slice::Iter<'_, Intersection<'a>> {
+    self.intersections.iter()
+  }
+
+  pub fn hit(&self) -> Option<&Intersection<'a>> {
+    self.intersections.iter().find(|i| i.t >= 0.0)
+  }
+}
+
+impl<'a> Index<usize> for Intersections<'a> {
+  type Output = Intersection<'a>;
+  fn index(&self, idx: usize) -> &Intersection<'a> {
+    &self.intersections[idx]
+  }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+  type Item = Intersection<'a>;
+  type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+  fn into_iter(self) -> Self::IntoIter {
+    self.intersections.into_iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::matrix::Mat4;
+  use super::super::sphere::{self, Sphere};
+  use super::super::plane::Plane;
+
+  #[test]
+  fn test_new() {
+    let s = Sphere::new();
+    let i = Intersection::new(3.5, &s);
+
+    assert!(float::eq(i.t(), 3.5));
+    assert!(std::ptr::addr_eq(i.object(), &s));
+  }
+
+  #[test]
+  fn test_with_uv() {
+    let s = Sphere::new();
+    let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+
+    assert!(float::eq(i.u(), 0.2));
+    assert!(float::eq(i.v(), 0.4));
+  }
+
+  #[test]
+  fn test_aggregate() {
+    let s = Sphere::new();
+    let i1 = Intersection::new(1.0, &s);
+    let i2 = Intersection::new(2.0, &s);
+    let xs = Intersections::new(vec![i1, i2]);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 1.0));
+    assert!(float::eq(xs[1].t(), 2.0));
+  }
+
+  #[test]
+  fn test_sorted() {
+    let s = Sphere::new();
+    let xs = Intersections::new(vec![
+      Intersection::new(5.0, &s),
+      Intersection::new(-3.0, &s),
+      Intersection::new(2.0, &s),
+    ]);
+
+    let ts: Vec<f32> = xs.iter().map(|i| i.t()).collect();
+    assert_eq!(ts, vec![-3.0, 2.0, 5.0]);
+  }
+
+  #[test]
+  fn test_hit_all_positive() {
+    let s = Sphere::new();
+    let i1 = Intersection::new(1.0, &s);
+    let i2 = Intersection::new(2.0, &s);
+    let xs = Intersections::new(vec![i2, i1]);
+
+    assert_eq!(xs.hit(), Some(&i1));
+  }
+
+  #[test]
+  fn test_hit_some_negative() {
+    let s = Sphere::new();
+    let i1 = Intersection::new(-1.0, &s);
+    let i2 = Intersection::new(1.0, &s);
+    let xs = Intersections::new(vec![i2, i1]);
+
+    assert_eq!(xs.hit(), Some(&i2));
+  }
+
+  #[test]
+  fn test_hit_all_negative() {
+    let s = Sphere::new();
+    let i1 = Intersection::new(-2.0, &s);
+    let i2 = Intersection::new(-1.0, &s);
+    let xs = Intersections::new(vec![i2, i1]);
+
+    assert_eq!(xs.hit(), None);
+  }
+
+  #[test]
+  fn test_hit_lowest_non_negative() {
+    let s = Sphere::new();
+    let i1 = Intersection::new(5.0, &s);
+    let i2 = Intersection::new(7.0, &s);
+    let i3 = Intersection::new(-3.0, &s);
+    let i4 = Intersection::new(2.0, &s);
+    let xs = Intersections::new(vec![i1, i2, i3, i4]);
+
+    assert_eq!(xs.hit(), Some(&i4));
+  }
+
+  #[test]
+  fn test_prepare_computations() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let i = Intersection::new(4.0, &s);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert!(float::eq(comps.t(), i.t()));
+    assert!(std::ptr::addr_eq(comps.object(), &s));
+    assert_eq!(comps.point(), Tuple::point(0.0, 0.0, -1.0));
+    assert_eq!(comps.eyev(), Tuple::vector(0.0, 0.0, -1.0));
+    assert_eq!(comps.normalv(), Tuple::vector(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_prepare_computations_outside() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let i = Intersection::new(4.0, &s);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert!(!comps.inside());
+  }
+
+  #[test]
+  fn test_prepare_computations_inside() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let i = Intersection::new(1.0, &s);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(comps.point(), Tuple::point(0.0, 0.0, 1.0));
+    assert_eq!(comps.eyev(), Tuple::vector(0.0, 0.0, -1.0));
+    assert!(comps.inside());
+    assert_eq!(comps.normalv(), Tuple::vector(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_prepare_computations_over_point() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(0.0, 0.0, 1.0));
+    let i = Intersection::new(5.0, &s);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert!(comps.over_point().z() < -float::OFFSET_EPSILON / 2.0);
+    assert!(comps.point().z() > comps.over_point().z());
+  }
+
+  #[test]
+  fn test_prepare_computations_reflectv() {
+    let p = Plane::new();
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 1.0, -1.0), Tuple::vector(0.0, -a, a));
+    let i = Intersection::new(2f32.sqrt(), &p);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(comps.reflectv(), Tuple::vector(0.0, a, a));
+  }
+
+  #[test]
+  fn test_refractive_indices() {
+    let mut a = sphere::glass_sphere();
+    a.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    let mut m = a.material().clone();
+    m.set_refractive_index(1.5);
+    a.set_material(m);
+
+    let mut b = sphere::glass_sphere();
+    b.set_transform(Mat4::translation(0.0, 0.0, -0.25));
+    let mut m = b.material().clone();
+    m.set_refractive_index(2.0);
+    b.set_material(m);
+
+    let mut c = sphere::glass_sphere();
+    c.set_transform(Mat4::translation(0.0, 0.0, 0.25));
+    let mut m = c.material().clone();
+    m.set_refractive_index(2.5);
+    c.set_material(m);
+
+    let r = Ray::new(Tuple::point(0.0, 0.0, -4.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = Intersections::new(vec![
+      Intersection::new(2.0, &a),
+      Intersection::new(2.75, &b),
+      Intersection::new(3.25, &c),
+      Intersection::new(4.75, &b),
+      Intersection::new(5.25, &c),
+      Intersection::new(6.0, &a),
+    ]);
+
+    let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+    for (idx, (n1, n2)) in expected.iter().enumerate() {
+      let comps = xs[idx].prepare_computations(&r, &xs);
+      assert!(float::eq(comps.n1(), *n1));
+      assert!(float::eq(comps.n2(), *n2));
+    }
+  }
+
+  #[test]
+  fn test_prepare_computations_under_point() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = sphere::glass_sphere();
+    s.set_transform(Mat4::translation(0.0, 0.0, 1.0));
+    let i = Intersection::new(5.0, &s);
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert!(comps.under_point().z() > float::OFFSET_EPSILON / 2.0);
+    assert!(comps.point().z() < comps.under_point().z());
+  }
+
+  #[test]
+  fn test_schlick_total_internal_reflection() {
+    let s = sphere::glass_sphere();
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, a), Tuple::vector(0.0, 1.0, 0.0));
+    let xs = Intersections::new(vec![Intersection::new(-a, &s), Intersection::new(a, &s)]);
+    let comps = xs[1].prepare_computations(&r, &xs);
+
+    assert!(float::eq(comps.schlick(), 1.0));
+  }
+
+  #[test]
+  fn test_schlick_perpendicular() {
+    let s = sphere::glass_sphere();
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+    let xs = Intersections::new(vec![Intersection::new(-1.0, &s), Intersection::new(1.0, &s)]);
+    let comps = xs[1].prepare_computations(&r, &xs);
+
+    assert!(float::eq(comps.schlick(), 0.04));
+  }
+
+  #[test]
+  fn test_schlick_small_angle() {
+    let s = sphere::glass_sphere();
+    let r = Ray::new(Tuple::point(0.0, 0.99, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = Intersections::new(vec![Intersection::new(1.8589, &s)]);
+    let comps = xs[0].prepare_computations(&r, &xs);
+
+    assert!(float::eq(comps.schlick(), 0.48873));
+  }
+}
@@ -0,0 +1,165 @@
+use super::tuple::Tuple;
+use super::color::Color;
+use super::material::Material;
+use super::shape::Shape;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointLight {
+  position: Tuple,
+  intensity: Color,
+}
+
+impl PointLight {
+  pub fn new(position: Tuple, intensity: Color) -> PointLight {
+    PointLight { position, intensity }
+  }
+
+  pub fn position(&self) -> Tuple {
+    self.position
+  }
+
+  pub fn intensity(&self) -> Color {
+    self.intensity
+  }
+}
+
+// Phong reflection: the sum of ambient, diffuse and specular contributions of
+// a single light at the given point. Shadowed points only get the ambient term.
+pub fn lighting(
+  material: &Material,
+  object: &dyn Shape,
+  light: &PointLight,
+  point: Tuple,
+  eyev: Tuple,
+  normalv: Tuple,
+  in_shadow: bool,
+) -> Color {
+  let black = Color::new(0.0, 0.0, 0.0);
+  let color = match material.pattern() {
+    Some(pattern) => pattern.pattern_at_shape(object, point),
+    None => material.color(),
+  };
+  let effective_color = color * light.intensity();
+  let lightv = (light.position() - point).normalize();
+  let ambient = effective_color * material.ambient();
+  if in_shadow {
+    return ambient;
+  }
+
+  // A negative dot product means the light is on the other side of the surface.
+  let light_dot_normal = lightv.dot(normalv);
+  if light_dot_normal < 0.0 {
+    return ambient;
+  }
+
+  let diffuse = effective_color * material.diffuse() * light_dot_normal;
+
+  // A negative dot product here means the light reflects away from the eye.
+  let reflectv = (-lightv).reflect(normalv);
+  let reflect_dot_eye = reflectv.dot(eyev);
+  let specular = if reflect_dot_eye <= 0.0 {
+    black
+  } else {
+    let factor = reflect_dot_eye.powf(material.shininess());
+    light.intensity() * material.specular() * factor
+  };
+
+  ambient + diffuse + specular
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::sphere::Sphere;
+  use super::super::pattern::StripePattern;
+
+  fn setup() -> (Material, Sphere, Tuple) {
+    (Material::new(), Sphere::new(), Tuple::point(0.0, 0.0, 0.0))
+  }
+
+  #[test]
+  fn test_new() {
+    let light = PointLight::new(Tuple::point(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(light.position(), Tuple::point(0.0, 0.0, 0.0));
+    assert_eq!(light.intensity(), Color::new(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_lighting_eye_between_light_and_surface() {
+    let (m, s, position) = setup();
+    let eyev = Tuple::vector(0.0, 0.0, -1.0);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, false), Color::new(1.9, 1.9, 1.9));
+  }
+
+  #[test]
+  fn test_lighting_eye_offset_45_degrees() {
+    let (m, s, position) = setup();
+    let a = 2f32.sqrt() / 2.0;
+    let eyev = Tuple::vector(0.0, a, -a);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, false), Color::new(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_lighting_light_offset_45_degrees() {
+    let (m, s, position) = setup();
+    let eyev = Tuple::vector(0.0, 0.0, -1.0);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, false), Color::new(0.7364, 0.7364, 0.7364));
+  }
+
+  #[test]
+  fn test_lighting_eye_in_reflection_path() {
+    let (m, s, position) = setup();
+    let a = 2f32.sqrt() / 2.0;
+    let eyev = Tuple::vector(0.0, -a, -a);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, false), Color::new(1.63639, 1.63639, 1.63639));
+  }
+
+  #[test]
+  fn test_lighting_light_behind_surface() {
+    let (m, s, position) = setup();
+    let eyev = Tuple::vector(0.0, 0.0, -1.0);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, false), Color::new(0.1, 0.1, 0.1));
+  }
+
+  #[test]
+  fn test_lighting_in_shadow() {
+    let (m, s, position) = setup();
+    let eyev = Tuple::vector(0.0, 0.0, -1.0);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    assert_eq!(lighting(&m, &s, &light, position, eyev, normalv, true), Color::new(0.1, 0.1, 0.1));
+  }
+
+  #[test]
+  fn test_lighting_with_pattern() {
+    let (mut m, s, _) = setup();
+    m.set_pattern(Box::new(StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))));
+    m.set_ambient(1.0);
+    m.set_diffuse(0.0);
+    m.set_specular(0.0);
+    let eyev = Tuple::vector(0.0, 0.0, -1.0);
+    let normalv = Tuple::vector(0.0, 0.0, -1.0);
+    let light = PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+
+    let c1 = lighting(&m, &s, &light, Tuple::point(0.9, 0.0, 0.0), eyev, normalv, false);
+    let c2 = lighting(&m, &s, &light, Tuple::point(1.1, 0.0, 0.0), eyev, normalv, false);
+    assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
+    assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
+  }
+}
@@ -4,39 +4,158 @@ mod tuple;
 mod float;
 mod color;
 mod canvas;
+mod ppm;
+mod zlib;
+mod png;
+mod hdr;
+mod pfm;
+mod tonemap;
 mod matrix;
+mod transform;
 mod ray;
+mod bounds;
+mod shape;
+mod sphere;
+mod plane;
+mod cube;
+mod cylinder;
+mod cone;
+mod group;
+mod triangle;
+mod smooth_triangle;
+mod csg;
+mod obj;
+mod pattern;
+mod intersection;
+mod material;
+mod light;
+mod world;
+mod camera;
+mod yaml;
+mod scene;
 
 use matrix::*;
 use tuple::*;
-use canvas::*;
 use color::*;
+use shape::*;
+use sphere::*;
+use plane::*;
+use pattern::*;
+use material::*;
+use light::*;
+use world::*;
+use camera::*;
+use ppm::*;
+use png::*;
+use tonemap::*;
+use scene::*;
 
 use std::f32::consts::{PI};
+use std::env;
 use std::fs::File;
-use std::io::prelude::*;
-
-fn main() -> std::io::Result<()> {
-    let clock_size = 200.0;
-    let padding = 20.0;
-    let dim = (clock_size * 2.0 + padding * 2.0) as usize;
-
-    let mut c = Canvas::new(dim, dim);
-    let p = Tuple::point(0.0, 0.0, 1.0);
-
-    for i in 0..12 {
-        let transform =
-            Mat4::identity()
-                .rotate_y(i as f32 * (PI/6.0))
-                .translate(1.0, 0.0, 1.0)
-                .scale(clock_size, 0.0, clock_size)
-                .translate(padding, 0.0, padding);
-        let np = transform * p;
-        let x = np.x() as usize;
-        let y = np.z() as usize;
-        c[(x, y)] = Color::new(255.0, 255.0, 255.0);
+use std::io::{self, BufWriter};
+use std::time::Instant;
+
+fn usage(message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{}\nusage: raytracerchallenge [--tone-map clamp|reinhard|reinhard:<white>|aces] [--exposure <stops>] [scene.yml]", message))
+}
+
+// Renders the scene file given as an argument, or the built-in scene if
+// there isn't one. The PPM and PNG output go through the tone map chosen with
+// --tone-map and --exposure; the HDR output keeps the full range.
+fn main() -> io::Result<()> {
+    let mut source = None;
+    let mut tone_map = ToneMap::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tone-map" => {
+                let value = args.next().ok_or_else(|| usage("--tone-map needs an operator".to_string()))?;
+                tone_map.set_operator(value.parse().map_err(usage)?);
+            },
+            "--exposure" => {
+                let value = args.next().ok_or_else(|| usage("--exposure needs a number of stops".to_string()))?;
+                // Past about 128 stops either way the scale overflows, and
+                // multiplying black by infinity gives NaN.
+                let stops: f32 = value.parse().ok()
+                    .filter(|stops: &f32| stops.is_finite() && 2f32.powf(*stops).is_finite())
+                    .ok_or_else(|| usage(format!("invalid exposure {:?}", value)))?;
+                tone_map.set_exposure(stops);
+            },
+            _ if arg.starts_with("--") => return Err(usage(format!("unknown option {}", arg))),
+            _ if source.is_none() => source = Some(arg),
+            _ => return Err(usage(format!("unexpected argument {}", arg))),
+        }
     }
 
-    File::create("out.ppm")?.write_all(c.to_ppm().as_bytes())?;
-    Ok(())
+    let (world, camera) = match &source {
+        Some(path) => Scene::from_file(path)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, err)))?
+            .into_parts(),
+        None => default_scene(),
+    };
+
+    let start = Instant::now();
+    let canvas = camera.render(&world);
+
+    let mut options = PpmOptions::new(PpmFormat::Raw);
+    options.add_comment(format!("scene: {}", source.as_deref().unwrap_or("built-in")));
+    options.add_comment(format!("render time: {:.2}s", start.elapsed().as_secs_f32()));
+    options.add_comment(format!("tone map: {:?}, exposure {:+} stops", tone_map.operator(), tone_map.exposure()));
+    let display = canvas.tone_mapped(&tone_map);
+    display.write_ppm(BufWriter::new(File::create("out.ppm")?), &options)?;
+    display.write_png(BufWriter::new(File::create("out.png")?), PngDepth::Eight)?;
+    canvas.write_hdr(BufWriter::new(File::create("out.hdr")?))
+}
+
+fn default_scene() -> (World, Camera) {
+    let mut world = World::new();
+    world.add_light(PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+
+    let mut wall_material = Material::new();
+    wall_material.set_color(Color::new(1.0, 0.9, 0.9));
+    wall_material.set_specular(0.0);
+
+    let mut floor_material = wall_material.clone();
+    floor_material.set_pattern(Box::new(CheckersPattern::new(
+        Color::new(1.0, 0.9, 0.9),
+        Color::new(0.6, 0.5, 0.5))));
+    floor_material.set_reflective(0.3);
+
+    let mut floor = Plane::new();
+    floor.set_material(floor_material);
+    world.add_object(Box::new(floor));
+
+    let mut back_wall = Plane::new();
+    back_wall.set_transform(Mat4::identity().rotate_x(PI / 2.0).translate(0.0, 0.0, 5.0));
+    back_wall.set_material(wall_material);
+    world.add_object(Box::new(back_wall));
+
+    let spheres = [
+        (Mat4::translation(-0.5, 1.0, 0.5), Color::new(0.1, 1.0, 0.5)),
+        (Mat4::identity().scale(0.5, 0.5, 0.5).translate(1.5, 0.5, -0.5), Color::new(0.5, 1.0, 0.1)),
+        (Mat4::identity().scale(0.33, 0.33, 0.33).translate(-1.5, 0.33, -0.75), Color::new(1.0, 0.8, 0.1)),
+    ];
+
+    for (transform, color) in spheres.iter() {
+        let mut sphere = Sphere::new();
+        sphere.set_transform(*transform);
+        let mut material = Material::new();
+        material.set_color(*color);
+        material.set_diffuse(0.7);
+        material.set_specular(0.3);
+        sphere.set_material(material);
+        world.add_object(Box::new(sphere));
+    }
+
+    let mut camera = Camera::new(200, 100, PI / 3.0);
+    camera.set_transform(Mat4::view_transform(
+        Tuple::point(0.0, 1.5, -5.0),
+        Tuple::point(0.0, 1.0, 0.0),
+        Tuple::vector(0.0, 1.0, 0.0)));
+
+    (world, camera)
 }
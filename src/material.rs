@@ -0,0 +1,188 @@
+use super::float;
+use super::color::Color;
+use super::pattern::Pattern;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct Material {
+  color: Color,
+  pattern: Option<Arc<dyn Pattern>>,
+  ambient: f32,
+  diffuse: f32,
+  specular: f32,
+  shininess: f32,
+  reflective: f32,
+  transparency: f32,
+  refractive_index: f32,
+}
+
+impl Default for Material {
+  fn default() -> Material {
+    Material::new()
+  }
+}
+
+impl Material {
+  pub fn new() -> Material {
+    Material {
+      color: Color::new(1.0, 1.0, 1.0),
+      pattern: None,
+      ambient: 0.1,
+      diffuse: 0.9,
+      specular: 0.9,
+      shininess: 200.0,
+      reflective: 0.0,
+      transparency: 0.0,
+      refractive_index: 1.0,
+    }
+  }
+
+  pub fn color(&self) -> Color {
+    self.color
+  }
+
+  // When set, the pattern is used in place of the flat colour.
+  pub fn pattern(&self) -> Option<&dyn Pattern> {
+    self.pattern.as_deref()
+  }
+
+  pub fn ambient(&self) -> f32 {
+    self.ambient
+  }
+
+  pub fn diffuse(&self) -> f32 {
+    self.diffuse
+  }
+
+  pub fn specular(&self) -> f32 {
+    self.specular
+  }
+
+  pub fn shininess(&self) -> f32 {
+    self.shininess
+  }
+
+  pub fn reflective(&self) -> f32 {
+    self.reflective
+  }
+
+  pub fn transparency(&self) -> f32 {
+    self.transparency
+  }
+
+  pub fn refractive_index(&self) -> f32 {
+    self.refractive_index
+  }
+
+  pub fn set_color(&mut self, color: Color) {
+    self.color = color;
+  }
+
+  pub fn set_pattern(&mut self, pattern: Box<dyn Pattern>) {
+    self.pattern = Some(Arc::from(pattern));
+  }
+
+  pub fn set_ambient(&mut self, ambient: f32) {
+    self.ambient = ambient;
+  }
+
+  pub fn set_diffuse(&mut self, diffuse: f32) {
+    self.diffuse = diffuse;
+  }
+
+  pub fn set_specular(&mut self, specular: f32) {
+    self.specular = specular;
+  }
+
+  pub fn set_shininess(&mut self, shininess: f32) {
+    self.shininess = shininess;
+  }
+
+  pub fn set_reflective(&mut self, reflective: f32) {
+    self.reflective = reflective;
+  }
+
+  pub fn set_transparency(&mut self, transparency: f32) {
+    self.transparency = transparency;
+  }
+
+  pub fn set_refractive_index(&mut self, refractive_index: f32) {
+    self.refractive_index = refractive_index;
+  }
+}
+
+// Patterns are compared by identity, everything else by value.
+impl PartialEq for Material {
+  fn eq(&self, other: &Material) -> bool {
+    let same_pattern = match (&self.pattern, &other.pattern) {
+      (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+      (None, None) => true,
+      _ => false,
+    };
+
+    same_pattern
+      && self.color == other.color
+      && float::eq(self.ambient, other.ambient)
+      && float::eq(self.diffuse, other.diffuse)
+      && float::eq(self.specular, other.specular)
+      && float::eq(self.shininess, other.shininess)
+      && float::eq(self.reflective, other.reflective)
+      && float::eq(self.transparency, other.transparency)
+      && float::eq(self.refractive_index, other.refractive_index)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::pattern::StripePattern;
+
+  #[test]
+  fn test_default() {
+    let m = Material::new();
+    assert_eq!(m.color(), Color::new(1.0, 1.0, 1.0));
+    assert!(m.pattern().is_none());
+    assert!(float::eq(m.ambient(), 0.1));
+    assert!(float::eq(m.diffuse(), 0.9));
+    assert!(float::eq(m.specular(), 0.9));
+    assert!(float::eq(m.shininess(), 200.0));
+    assert!(float::eq(m.reflective(), 0.0));
+    assert!(float::eq(m.transparency(), 0.0));
+    assert!(float::eq(m.refractive_index(), 1.0));
+  }
+
+  #[test]
+  fn test_setters() {
+    let mut m = Material::new();
+    m.set_color(Color::new(1.0, 0.2, 1.0));
+    m.set_ambient(1.0);
+    m.set_diffuse(0.7);
+    m.set_specular(0.3);
+    m.set_shininess(10.0);
+    m.set_reflective(0.5);
+    m.set_transparency(0.25);
+    m.set_refractive_index(1.33);
+
+    assert_eq!(m.color(), Color::new(1.0, 0.2, 1.0));
+    assert!(float::eq(m.ambient(), 1.0));
+    assert!(float::eq(m.diffuse(), 0.7));
+    assert!(float::eq(m.specular(), 0.3));
+    assert!(float::eq(m.shininess(), 10.0));
+    assert!(float::eq(m.reflective(), 0.5));
+    assert!(float::eq(m.transparency(), 0.25));
+    assert!(float::eq(m.refractive_index(), 1.33));
+  }
+
+  #[test]
+  fn test_eq_compares_pattern_identity() {
+    let mut a = Material::new();
+    a.set_pattern(Box::new(StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))));
+    let b = a.clone();
+    let mut c = Material::new();
+    c.set_pattern(Box::new(StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))));
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_ne!(a, Material::new());
+  }
+}
@@ -65,7 +65,7 @@ impl PartialEq for Mat4 {
       }
     }
 
-    return true
+    true
   }
 }
 impl Eq for Mat4 {}
@@ -78,7 +78,7 @@ impl PartialEq for Mat3 {
       }
     }
 
-    return true
+    true
   }
 }
 impl Eq for Mat3 {}
@@ -91,7 +91,7 @@ impl PartialEq for Mat2 {
       }
     }
 
-    return true
+    true
   }
 }
 impl Eq for Mat2 {}
@@ -198,6 +198,19 @@ impl Mat4 {
     ] }
   }
 
+  pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Mat4 {
+    let forward = (to - from).normalize();
+    let left = forward * up.normalize();
+    let true_up = left * forward;
+    let orientation = Mat4 { matrix: [
+      left.x(), left.y(), left.z(), 0.0,
+      true_up.x(), true_up.y(), true_up.z(), 0.0,
+      -forward.x(), -forward.y(), -forward.z(), 0.0,
+      0.0, 0.0, 0.0, 1.0,
+    ] };
+    orientation * Mat4::translation(-from.x(), -from.y(), -from.z())
+  }
+
   pub fn identity() -> Mat4 {
     Mat4::new([
       1.0, 0.0, 0.0, 0.0,
@@ -258,8 +271,10 @@ impl Mat4 {
     total
   }
 
+  // Exact, as scaling a model down to a hundredth already gives a
+  // determinant far below EPSILON while inverting perfectly well.
   pub fn is_invertible(&self) -> bool {
-    !float::eq(self.determinant(), 0.0)
+    self.determinant() != 0.0
   }
 
   pub fn inverse(&self) -> Mat4 {
@@ -970,4 +985,47 @@ mod tests {
 
     assert_eq!(chain * p, Tuple::point(15.0, 0.0, 7.0));
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_view_transform_default() {
+    let from = Tuple::point(0.0, 0.0, 0.0);
+    let to = Tuple::point(0.0, 0.0, -1.0);
+    let up = Tuple::vector(0.0, 1.0, 0.0);
+
+    assert_eq!(Mat4::view_transform(from, to, up), Mat4::identity());
+  }
+
+  #[test]
+  fn test_view_transform_positive_z() {
+    let from = Tuple::point(0.0, 0.0, 0.0);
+    let to = Tuple::point(0.0, 0.0, 1.0);
+    let up = Tuple::vector(0.0, 1.0, 0.0);
+
+    assert_eq!(Mat4::view_transform(from, to, up), Mat4::scaling(-1.0, 1.0, -1.0));
+  }
+
+  #[test]
+  fn test_view_transform_moves_world() {
+    let from = Tuple::point(0.0, 0.0, 8.0);
+    let to = Tuple::point(0.0, 0.0, 0.0);
+    let up = Tuple::vector(0.0, 1.0, 0.0);
+
+    assert_eq!(Mat4::view_transform(from, to, up), Mat4::translation(0.0, 0.0, -8.0));
+  }
+
+  #[test]
+  fn test_view_transform_arbitrary() {
+    let from = Tuple::point(1.0, 3.0, 2.0);
+    let to = Tuple::point(4.0, -2.0, 8.0);
+    let up = Tuple::vector(1.0, 1.0, 0.0);
+
+    let result = Mat4::new([
+      -0.50709, 0.50709, 0.67612, -2.36643,
+      0.76772, 0.60609, 0.12122, -2.82843,
+      -0.35857, 0.59761, -0.71714, 0.00000,
+      0.00000, 0.00000, 0.00000, 1.00000,
+    ]);
+
+    assert_eq!(Mat4::view_transform(from, to, up), result);
+  }
+}
@@ -0,0 +1,413 @@
+use super::tuple::Tuple;
+use super::shape::Shape;
+use super::group::Group;
+use super::triangle::Triangle;
+use super::smooth_triangle::SmoothTriangle;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// A line of an OBJ file that couldn't be used, and why. Parsing carries on
+// past these so one bad record doesn't lose the rest of a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjError {
+  line: usize,
+  message: String,
+}
+
+impl ObjError {
+  fn new(line: usize, message: impl Into<String>) -> ObjError {
+    ObjError { line, message: message.into() }
+  }
+
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+}
+
+impl fmt::Display for ObjError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for ObjError {}
+
+// One corner of a face: indices into the vertex, texture and normal lists,
+// already resolved to 0-based.
+#[derive(Debug, Copy, Clone)]
+struct FaceVertex {
+  vertex: usize,
+  normal: Option<usize>,
+}
+
+// Where entries had to be skipped from one of the indexed lists, as the
+// position each bad entry would have had and its line. Indices that count
+// across a skipped entry no longer point where the file meant them to.
+#[derive(Debug, Default, Copy, Clone)]
+struct Skipped {
+  first: Option<(usize, usize)>,
+  latest: Option<(usize, usize)>,
+}
+
+impl Skipped {
+  fn add(&mut self, position: usize, line: usize) {
+    if self.first.is_none() {
+      self.first = Some((position, line));
+    }
+    self.latest = Some((position, line));
+  }
+}
+
+// The result of reading a Wavefront OBJ file. Faces before any g or o record
+// go into the default group; the rest go into the group they were named under.
+#[derive(Debug)]
+pub struct ObjFile {
+  vertices: Vec<Tuple>,
+  normals: Vec<Tuple>,
+  texture_coords: Vec<(f32, f32)>,
+  default_group: Group,
+  groups: Vec<(String, Group)>,
+  ignored: Vec<ObjError>,
+  skipped_vertices: Skipped,
+  skipped_normals: Skipped,
+  skipped_texture_coords: Skipped,
+}
+
+impl ObjFile {
+  pub fn parse(input: &str) -> ObjFile {
+    let mut obj = ObjFile {
+      vertices: vec![],
+      normals: vec![],
+      texture_coords: vec![],
+      default_group: Group::new(),
+      groups: vec![],
+      ignored: vec![],
+      skipped_vertices: Skipped::default(),
+      skipped_normals: Skipped::default(),
+      skipped_texture_coords: Skipped::default(),
+    };
+    let mut current: Option<usize> = None;
+
+    for (idx, line) in input.lines().enumerate() {
+      let mut words = line.split_whitespace();
+      let keyword = match words.next() {
+        Some(keyword) if !keyword.starts_with('#') => keyword,
+        _ => continue,
+      };
+      let args: Vec<&str> = words.collect();
+
+      let result = match keyword {
+        "v" => parse_floats(&args, 3, 3)
+          .map(|n| obj.vertices.push(Tuple::point(n[0], n[1], n[2])))
+          .inspect_err(|_| obj.skipped_vertices.add(obj.vertices.len(), idx + 1)),
+        "vn" => parse_floats(&args, 3, 3)
+          .map(|n| obj.normals.push(Tuple::vector(n[0], n[1], n[2])))
+          .inspect_err(|_| obj.skipped_normals.add(obj.normals.len(), idx + 1)),
+        // v and w are optional and default to 0.
+        "vt" => parse_floats(&args, 1, 3)
+          .map(|n| obj.texture_coords.push((n[0], n.get(1).copied().unwrap_or(0.0))))
+          .inspect_err(|_| obj.skipped_texture_coords.add(obj.texture_coords.len(), idx + 1)),
+        "f" => obj.parse_face(&args).map(|triangles| {
+          let group = match current {
+            Some(i) => &mut obj.groups[i].1,
+            None => &mut obj.default_group,
+          };
+          for triangle in triangles {
+            group.add_child(triangle);
+          }
+        }),
+        "g" | "o" => match args.first() {
+          Some(name) => {
+            current = Some(obj.group_index(name));
+            Ok(())
+          },
+          None => Err(format!("{} needs a name", keyword)),
+        },
+        _ => Err(format!("unsupported statement {:?}", keyword)),
+      };
+
+      if let Err(message) = result {
+        obj.ignored.push(ObjError::new(idx + 1, message));
+      }
+    }
+
+    obj
+  }
+
+  pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<ObjFile> {
+    Ok(ObjFile::parse(&fs::read_to_string(path)?))
+  }
+
+  pub fn vertices(&self) -> &[Tuple] {
+    &self.vertices
+  }
+
+  pub fn normals(&self) -> &[Tuple] {
+    &self.normals
+  }
+
+  pub fn texture_coords(&self) -> &[(f32, f32)] {
+    &self.texture_coords
+  }
+
+  pub fn default_group(&self) -> &Group {
+    &self.default_group
+  }
+
+  pub fn group(&self, name: &str) -> Option<&Group> {
+    self.groups.iter().find(|(n, _)| n == name).map(|(_, g)| g)
+  }
+
+  pub fn ignored(&self) -> &[ObjError] {
+    &self.ignored
+  }
+
+  // Everything in the file as one group, with each named group nested inside.
+  pub fn into_group(self) -> Group {
+    let mut group = self.default_group;
+    for (_, child) in self.groups {
+      group.add_child(Box::new(child));
+    }
+    group
+  }
+
+  fn group_index(&mut self, name: &str) -> usize {
+    match self.groups.iter().position(|(n, _)| n == name) {
+      Some(i) => i,
+      None => {
+        self.groups.push((name.to_string(), Group::new()));
+        self.groups.len() - 1
+      },
+    }
+  }
+
+  // Polygons are split into a fan of triangles around their first vertex.
+  // Faces are smooth only if every corner has a normal.
+  fn parse_face(&self, args: &[&str]) -> Result<Vec<Box<dyn Shape>>, String> {
+    if args.len() < 3 {
+      return Err(format!("face needs at least 3 vertices, found {}", args.len()));
+    }
+
+    let corners = args.iter().map(|arg| self.parse_face_vertex(arg)).collect::<Result<Vec<_>, _>>()?;
+    let smooth = corners.iter().all(|c| c.normal.is_some());
+
+    let mut triangles: Vec<Box<dyn Shape>> = Vec::with_capacity(corners.len() - 2);
+    for i in 1..corners.len() - 1 {
+      let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+      let (p1, p2, p3) = (self.vertices[a.vertex], self.vertices[b.vertex], self.vertices[c.vertex]);
+
+      if smooth {
+        let (n1, n2, n3) = (self.normals[a.normal.unwrap()], self.normals[b.normal.unwrap()], self.normals[c.normal.unwrap()]);
+        triangles.push(Box::new(SmoothTriangle::new(p1, p2, p3, n1, n2, n3)));
+      } else {
+        triangles.push(Box::new(Triangle::new(p1, p2, p3)));
+      }
+    }
+
+    Ok(triangles)
+  }
+
+  // Accepts v, v/vt, v//vn and v/vt/vn.
+  fn parse_face_vertex(&self, arg: &str) -> Result<FaceVertex, String> {
+    let mut parts = arg.split('/');
+    let vertex = resolve_index(parts.next().unwrap_or(""), self.vertices.len(), &self.skipped_vertices, "vertex")?;
+
+    match parts.next() {
+      None | Some("") => {},
+      Some(texture) => {
+        resolve_index(texture, self.texture_coords.len(), &self.skipped_texture_coords, "texture coordinate")?;
+      },
+    }
+
+    let normal = match parts.next() {
+      None | Some("") => None,
+      Some(normal) => Some(resolve_index(normal, self.normals.len(), &self.skipped_normals, "normal")?),
+    };
+
+    if parts.next().is_some() {
+      return Err(format!("malformed face vertex {:?}", arg));
+    }
+
+    Ok(FaceVertex { vertex, normal })
+  }
+}
+
+fn parse_floats(args: &[&str], min: usize, max: usize) -> Result<Vec<f32>, String> {
+  if args.len() < min {
+    return Err(format!("expected {} numbers, found {}", min, args.len()));
+  }
+
+  // Anything past the numbers we need, like a w component, is skipped.
+  args[..args.len().min(max)].iter()
+    .map(|arg| arg.parse::<f32>().map_err(|_| format!("invalid number {:?}", arg)))
+    .collect()
+}
+
+// OBJ indices start at 1, and negative ones count back from the latest entry.
+// Either kind is refused if it would count across a skipped entry, as it'd
+// silently pick the wrong one.
+fn resolve_index(arg: &str, len: usize, skipped: &Skipped, kind: &str) -> Result<usize, String> {
+  let index: i64 = arg.parse().map_err(|_| format!("invalid {} index {:?}", kind, arg))?;
+  let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+
+  if index == 0 || resolved < 0 || resolved >= len as i64 {
+    return Err(format!("{} index {} out of range", kind, index));
+  }
+  let resolved = resolved as usize;
+
+  match (skipped.first, skipped.latest) {
+    (Some((position, line)), _) if index > 0 && resolved >= position =>
+      Err(format!("{} index {} comes after the bad {} on line {}", kind, index, kind, line)),
+    (_, Some((position, line))) if index < 0 && resolved < position =>
+      Err(format!("{} index {} counts back past the bad {} on line {}", kind, index, kind, line)),
+    _ => Ok(resolved),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::ray::Ray;
+
+  #[test]
+  fn test_ignores_unrecognised_lines() {
+    let obj = ObjFile::parse("There was a young lady named Bright\n\
+                              who traveled much faster than light.\n");
+
+    assert_eq!(obj.ignored().len(), 2);
+    assert_eq!(obj.ignored()[1].line(), 2);
+    assert!(obj.default_group().is_empty());
+  }
+
+  #[test]
+  fn test_vertices() {
+    let obj = ObjFile::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n");
+
+    assert_eq!(obj.vertices(), &[
+      Tuple::point(-1.0, 1.0, 0.0),
+      Tuple::point(-1.0, 0.5, 0.0),
+      Tuple::point(1.0, 0.0, 0.0),
+      Tuple::point(1.0, 1.0, 0.0),
+    ]);
+  }
+
+  #[test]
+  fn test_triangle_faces() {
+    let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n");
+    let g = obj.default_group();
+
+    assert!(obj.ignored().is_empty());
+    assert_eq!(g.children().len(), 2);
+  }
+
+  #[test]
+  fn test_polygon_fan() {
+    let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n");
+    assert_eq!(obj.default_group().children().len(), 3);
+
+    // The last triangle of the fan is (1, 4, 5), which covers (0, 1.5).
+    let r = Ray::new(Tuple::point(0.0, 1.5, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let hits: Vec<usize> = obj.default_group().children().iter()
+      .map(|c| c.intersect(&r).len())
+      .collect();
+    assert_eq!(hits, vec![0, 0, 1]);
+  }
+
+  #[test]
+  fn test_named_groups() {
+    let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                              g FirstGroup\nf 1 2 3\no SecondGroup\nf 1 3 4\n");
+
+    assert!(obj.default_group().is_empty());
+    assert_eq!(obj.group("FirstGroup").unwrap().children().len(), 1);
+    assert_eq!(obj.group("SecondGroup").unwrap().children().len(), 1);
+    assert!(obj.group("ThirdGroup").is_none());
+  }
+
+  #[test]
+  fn test_into_group() {
+    let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                              f 1 2 3\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n");
+    let g = obj.into_group();
+
+    assert_eq!(g.children().len(), 3);
+  }
+
+  #[test]
+  fn test_vertex_normals() {
+    let obj = ObjFile::parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\n");
+
+    assert_eq!(obj.normals(), &[
+      Tuple::vector(0.0, 0.0, 1.0),
+      Tuple::vector(0.707, 0.0, -0.707),
+      Tuple::vector(1.0, 2.0, 3.0),
+    ]);
+  }
+
+  #[test]
+  fn test_faces_with_normals() {
+    let obj = ObjFile::parse("v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                              vn -1 0 0\nvn 1 0 0\nvn 0 1 0\nvt 0.5 0.5\n\
+                              f 1//3 2//1 3//2\nf 1/1/3 2/1/1 3/1/2\n");
+    let g = obj.default_group();
+
+    assert!(obj.ignored().is_empty());
+    assert_eq!(obj.texture_coords(), &[(0.5, 0.5)]);
+    assert_eq!(g.children().len(), 2);
+
+    // (-0.5, 0.25) is at u = 0.625, v = 0.125, so the interpolated normal
+    // is 0.625 n2 + 0.125 n3 + 0.25 n1 rather than the flat face normal.
+    let r = Ray::new(Tuple::point(-0.5, 0.25, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    for child in g.children() {
+      let xs = child.intersect(&r);
+      let n = child.normal_at_hit(r.position_at_time(xs[0].t()), &xs[0]);
+      assert_eq!(n, Tuple::vector(-0.5, 0.25, 0.0).normalize());
+    }
+  }
+
+  #[test]
+  fn test_bad_lines_are_reported() {
+    let obj = ObjFile::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 2\nv 1 1 0\n\
+                              f 1 2 9\nf 1 2\nf 1 2 3\nf 1 2 4\nf 1 2 -1\nf 1 2 -2\ng\n");
+    let lines: Vec<usize> = obj.ignored().iter().map(|e| e.line()).collect();
+
+    assert_eq!(lines, vec![4, 6, 7, 9, 11, 12]);
+    assert_eq!(obj.ignored()[1].to_string(), "line 6: vertex index 9 out of range");
+    assert_eq!(obj.vertices().len(), 4);
+  }
+
+  #[test]
+  fn test_indices_past_bad_lines_are_refused() {
+    let obj = ObjFile::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 2\nv 1 1 0\n\
+                              f 1 2 3\nf 1 2 4\nf 1 2 -1\nf 1 2 -2\n");
+
+    assert_eq!(obj.ignored()[1].to_string(), "line 7: vertex index 4 comes after the bad vertex on line 4");
+    assert_eq!(obj.ignored()[2].to_string(), "line 9: vertex index -2 counts back past the bad vertex on line 4");
+
+    // Only the faces before the bad vertex, and the one counting back to
+    // the vertex after it, are kept.
+    let children = obj.default_group().children();
+    assert_eq!(children.len(), 2);
+    let r = Ray::new(Tuple::point(0.9, 0.5, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(children[0].intersect(&r).is_empty());
+    assert_eq!(children[1].intersect(&r).len(), 1);
+  }
+
+  #[test]
+  fn test_texture_coords() {
+    let obj = ObjFile::parse("vt 0.25\nvt 0.5 0.75\nvt 0.1 0.2 0.3\nvt\nvn 0 0 1\nvn x\n\
+                              v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/4/1 2/4/1 3/4/1\nf 1/1/2 2/1/2 3/1/2\n");
+
+    assert_eq!(obj.texture_coords(), &[(0.25, 0.0), (0.5, 0.75), (0.1, 0.2)]);
+    let lines: Vec<usize> = obj.ignored().iter().map(|e| e.line()).collect();
+    assert_eq!(lines, vec![4, 6, 10, 11]);
+    assert_eq!(obj.ignored()[2].message(), "texture coordinate index 4 out of range");
+    assert_eq!(obj.ignored()[3].message(), "normal index 2 out of range");
+  }
+}
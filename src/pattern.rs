@@ -0,0 +1,318 @@
+use super::float;
+use super::tuple::Tuple;
+use super::color::Color;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::shape::Shape;
+use std::fmt;
+
+// A colour that varies over space. Like shapes, patterns have their own
+// transform and only need to answer pattern_at in their own pattern space.
+pub trait Pattern: fmt::Debug + Send + Sync {
+  fn transform(&self) -> &Transform;
+  fn set_transform(&mut self, transform: Mat4);
+
+  fn pattern_at(&self, point: Tuple) -> Color;
+
+  fn pattern_at_shape(&self, object: &dyn Shape, world_point: Tuple) -> Color {
+    let object_point = object.world_to_object(world_point);
+    let pattern_point = *self.transform().inverse() * object_point;
+    self.pattern_at(pattern_point)
+  }
+}
+
+// Points that land a hair below an integer boundary, as hits on an axis-aligned
+// plane often do, would otherwise flicker between cells.
+fn cell(n: f32) -> i64 {
+  (n + float::EPSILON).floor() as i64
+}
+
+#[derive(Debug, Clone)]
+pub struct StripePattern {
+  a: Color,
+  b: Color,
+  transform: Transform,
+}
+
+impl StripePattern {
+  pub fn new(a: Color, b: Color) -> StripePattern {
+    StripePattern { a, b, transform: Transform::identity() }
+  }
+
+  pub fn a(&self) -> Color {
+    self.a
+  }
+
+  pub fn b(&self) -> Color {
+    self.b
+  }
+}
+
+impl Pattern for StripePattern {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn pattern_at(&self, point: Tuple) -> Color {
+    if cell(point.x()).rem_euclid(2) == 0 {
+      self.a
+    } else {
+      self.b
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct GradientPattern {
+  a: Color,
+  b: Color,
+  transform: Transform,
+}
+
+impl GradientPattern {
+  pub fn new(a: Color, b: Color) -> GradientPattern {
+    GradientPattern { a, b, transform: Transform::identity() }
+  }
+}
+
+impl Pattern for GradientPattern {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn pattern_at(&self, point: Tuple) -> Color {
+    let distance = self.b - self.a;
+    let fraction = point.x() - point.x().floor();
+    self.a + distance * fraction
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RingPattern {
+  a: Color,
+  b: Color,
+  transform: Transform,
+}
+
+impl RingPattern {
+  pub fn new(a: Color, b: Color) -> RingPattern {
+    RingPattern { a, b, transform: Transform::identity() }
+  }
+}
+
+impl Pattern for RingPattern {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn pattern_at(&self, point: Tuple) -> Color {
+    let distance = (point.x().powi(2) + point.z().powi(2)).sqrt();
+    if cell(distance).rem_euclid(2) == 0 {
+      self.a
+    } else {
+      self.b
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckersPattern {
+  a: Color,
+  b: Color,
+  transform: Transform,
+}
+
+impl CheckersPattern {
+  pub fn new(a: Color, b: Color) -> CheckersPattern {
+    CheckersPattern { a, b, transform: Transform::identity() }
+  }
+}
+
+impl Pattern for CheckersPattern {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn pattern_at(&self, point: Tuple) -> Color {
+    let sum = cell(point.x()) + cell(point.y()) + cell(point.z());
+    if sum.rem_euclid(2) == 0 {
+      self.a
+    } else {
+      self.b
+    }
+  }
+}
+
+// Reports the point it was asked about as a colour, so tests can see exactly
+// which pattern-space point a lookup ended up at.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct TestPattern {
+  transform: Transform,
+}
+
+#[cfg(test)]
+impl Pattern for TestPattern {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn pattern_at(&self, point: Tuple) -> Color {
+    Color::new(point.x(), point.y(), point.z())
+  }
+}
+
+#[cfg(test)]
+pub fn test_pattern() -> TestPattern {
+  TestPattern { transform: Transform::identity() }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::sphere::Sphere;
+
+  fn white() -> Color {
+    Color::new(1.0, 1.0, 1.0)
+  }
+
+  fn black() -> Color {
+    Color::new(0.0, 0.0, 0.0)
+  }
+
+  #[test]
+  fn test_default_transform() {
+    let p = test_pattern();
+    assert_eq!(*p.transform().matrix(), Mat4::identity());
+  }
+
+  #[test]
+  fn test_object_transformation() {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    let p = test_pattern();
+
+    assert_eq!(p.pattern_at_shape(&s, Tuple::point(2.0, 3.0, 4.0)), Color::new(1.0, 1.5, 2.0));
+  }
+
+  #[test]
+  fn test_pattern_transformation() {
+    let s = Sphere::new();
+    let mut p = test_pattern();
+    p.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+
+    assert_eq!(p.pattern_at_shape(&s, Tuple::point(2.0, 3.0, 4.0)), Color::new(1.0, 1.5, 2.0));
+  }
+
+  #[test]
+  fn test_object_and_pattern_transformation() {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    let mut p = test_pattern();
+    p.set_transform(Mat4::translation(0.5, 1.0, 1.5));
+
+    assert_eq!(p.pattern_at_shape(&s, Tuple::point(2.5, 3.0, 3.5)), Color::new(0.75, 0.5, 0.25));
+  }
+
+  #[test]
+  fn test_stripe_new() {
+    let p = StripePattern::new(white(), black());
+    assert_eq!(p.a(), white());
+    assert_eq!(p.b(), black());
+  }
+
+  #[test]
+  fn test_stripe_constant_in_y() {
+    let p = StripePattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 1.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 2.0, 0.0)), white());
+  }
+
+  #[test]
+  fn test_stripe_constant_in_z() {
+    let p = StripePattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 1.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 2.0)), white());
+  }
+
+  #[test]
+  fn test_stripe_alternates_in_x() {
+    let p = StripePattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.9, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(1.0, 0.0, 0.0)), black());
+    assert_eq!(p.pattern_at(Tuple::point(-0.1, 0.0, 0.0)), black());
+    assert_eq!(p.pattern_at(Tuple::point(-1.0, 0.0, 0.0)), black());
+    assert_eq!(p.pattern_at(Tuple::point(-1.1, 0.0, 0.0)), white());
+  }
+
+  #[test]
+  fn test_gradient() {
+    let p = GradientPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+    assert_eq!(p.pattern_at(Tuple::point(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+    assert_eq!(p.pattern_at(Tuple::point(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
+  }
+
+  #[test]
+  fn test_ring() {
+    let p = RingPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(1.0, 0.0, 0.0)), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 1.0)), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.708, 0.0, 0.708)), black());
+  }
+
+  #[test]
+  fn test_checkers_repeat_in_x() {
+    let p = CheckersPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.99, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(1.01, 0.0, 0.0)), black());
+  }
+
+  #[test]
+  fn test_checkers_repeat_in_y() {
+    let p = CheckersPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.99, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 1.01, 0.0)), black());
+  }
+
+  #[test]
+  fn test_checkers_repeat_in_z() {
+    let p = CheckersPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.0)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 0.99)), white());
+    assert_eq!(p.pattern_at(Tuple::point(0.0, 0.0, 1.01)), black());
+  }
+
+  #[test]
+  fn test_checkers_just_below_boundary() {
+    let p = CheckersPattern::new(white(), black());
+    assert_eq!(p.pattern_at(Tuple::point(0.5, -0.000001, 0.5)), white());
+  }
+}
@@ -0,0 +1,211 @@
+use super::color::Color;
+use super::canvas::Canvas;
+use std::fmt;
+use std::io::{self, Write};
+
+// Portable Float Map keeps every channel as a 32-bit float, so nothing is
+// lost at all. Rows are stored bottom to top, and the sign of the scale in
+// the header gives the byte order: negative means little-endian.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PfmError {
+  // The file doesn't start with PF (colour) or Pf (greyscale).
+  UnsupportedFormat,
+  // A header field that can't be parsed, or a scale of zero.
+  InvalidHeader { offset: usize },
+  ZeroSize,
+  UnexpectedEof,
+}
+
+impl fmt::Display for PfmError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PfmError::UnsupportedFormat => write!(f, "not a PF or Pf float map"),
+      PfmError::InvalidHeader { offset } => write!(f, "invalid header field at byte {}", offset),
+      PfmError::ZeroSize => write!(f, "image has no pixels"),
+      PfmError::UnexpectedEof => write!(f, "file ends before the image does"),
+    }
+  }
+}
+
+impl std::error::Error for PfmError {}
+
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  // Returns the next whitespace separated token and where it starts.
+  fn token(&mut self) -> Result<(&'a str, usize), PfmError> {
+    while self.data.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+      self.pos += 1;
+    }
+    let start = self.pos;
+    while self.data.get(self.pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+      self.pos += 1;
+    }
+    if start == self.pos {
+      return Err(PfmError::UnexpectedEof);
+    }
+    let token = std::str::from_utf8(&self.data[start..self.pos])
+      .map_err(|_| PfmError::InvalidHeader { offset: start })?;
+    Ok((token, start))
+  }
+
+  fn parse<T: std::str::FromStr>(&mut self) -> Result<T, PfmError> {
+    let (token, offset) = self.token()?;
+    token.parse().map_err(|_| PfmError::InvalidHeader { offset })
+  }
+}
+
+impl Canvas {
+  // Always writes little-endian colour maps with a scale of -1.
+  pub fn write_pfm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    write!(writer, "PF\n{} {}\n-1.0\n", self.width(), self.height())?;
+
+    let mut row = Vec::with_capacity(self.width() * 12);
+    for y in (0..self.height()).rev() {
+      row.clear();
+      for x in 0..self.width() {
+        let pixel = self[(x, y)];
+        for channel in [pixel.red(), pixel.green(), pixel.blue()].iter() {
+          row.extend_from_slice(&channel.to_le_bytes());
+        }
+      }
+      writer.write_all(&row)?;
+    }
+
+    writer.flush()
+  }
+
+  // Reads colour and greyscale maps in either byte order. The magnitude of
+  // the scale isn't applied, as most tools ignore it too.
+  pub fn from_pfm(data: &[u8]) -> Result<Canvas, PfmError> {
+    let channels = match data.get(..2) {
+      Some(b"PF") => 3,
+      Some(b"Pf") => 1,
+      _ => return Err(PfmError::UnsupportedFormat),
+    };
+    if !data.get(2).is_some_and(|c| c.is_ascii_whitespace()) {
+      return Err(PfmError::UnsupportedFormat);
+    }
+
+    let mut reader = Reader { data, pos: 2 };
+    let width: usize = reader.parse()?;
+    let height: usize = reader.parse()?;
+    let (token, offset) = reader.token()?;
+    let scale: f32 = token.parse().map_err(|_| PfmError::InvalidHeader { offset })?;
+
+    if scale == 0.0 || !scale.is_finite() {
+      return Err(PfmError::InvalidHeader { offset });
+    }
+    if width == 0 || height == 0 {
+      return Err(PfmError::ZeroSize);
+    }
+
+    // Exactly one whitespace byte separates the header from the raster.
+    if !data.get(reader.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+      return Err(PfmError::UnexpectedEof);
+    }
+    reader.pos += 1;
+
+    let little_endian = scale < 0.0;
+    // A size too big to count can't be in the file either.
+    let end = width.checked_mul(height)
+      .and_then(|n| n.checked_mul(channels * 4))
+      .and_then(|len| reader.pos.checked_add(len))
+      .ok_or(PfmError::UnexpectedEof)?;
+    let raster = data.get(reader.pos..end).ok_or(PfmError::UnexpectedEof)?;
+
+    let mut canvas = Canvas::new(width, height);
+    for (i, pixel) in raster.chunks_exact(channels * 4).enumerate() {
+      let mut samples = [0.0; 3];
+      for (sample, bytes) in samples.iter_mut().zip(pixel.chunks_exact(4)) {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        *sample = if little_endian { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) };
+      }
+      if channels == 1 {
+        samples = [samples[0]; 3];
+      }
+      canvas[(i % width, height - 1 - i / width)] = Color::new(samples[0], samples[1], samples[2]);
+    }
+
+    Ok(canvas)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_write_pfm() {
+    let mut canvas = Canvas::new(2, 2);
+    canvas[(0, 0)] = Color::new(1.0, 2.0, 3.0);
+    canvas[(1, 1)] = Color::new(-0.5, 1000.0, 0.0);
+
+    let mut buf = Vec::new();
+    canvas.write_pfm(&mut buf).unwrap();
+
+    let header = b"PF\n2 2\n-1.0\n";
+    assert_eq!(&buf[..header.len()], header);
+    assert_eq!(buf.len(), header.len() + 2 * 2 * 12);
+
+    // The bottom row comes first.
+    let raster = &buf[header.len()..];
+    assert_eq!(&raster[12..16], &(-0.5f32).to_le_bytes());
+    assert_eq!(&raster[24..28], &1.0f32.to_le_bytes());
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas[(0, 0)] = Color::new(123.456, 0.001, -7.0);
+    canvas[(2, 1)] = Color::new(1e20, f32::INFINITY, 0.5);
+
+    let mut buf = Vec::new();
+    canvas.write_pfm(&mut buf).unwrap();
+    let read = Canvas::from_pfm(&buf).unwrap();
+
+    assert_eq!(read.width(), 3);
+    assert_eq!(read.height(), 2);
+    for y in 0..2 {
+      for x in 0..3 {
+        let (a, b) = (canvas[(x, y)], read[(x, y)]);
+        assert_eq!([a.red(), a.green(), a.blue()], [b.red(), b.green(), b.blue()]);
+      }
+    }
+  }
+
+  #[test]
+  fn test_read_big_endian_greyscale() {
+    let mut data = b"Pf\n1 2\n1.0\n".to_vec();
+    data.extend_from_slice(&0.25f32.to_be_bytes());
+    data.extend_from_slice(&4.0f32.to_be_bytes());
+
+    let canvas = Canvas::from_pfm(&data).unwrap();
+    assert_eq!(canvas[(0, 0)], Color::new(4.0, 4.0, 4.0));
+    assert_eq!(canvas[(0, 1)], Color::new(0.25, 0.25, 0.25));
+  }
+
+  #[test]
+  fn test_errors() {
+    let examples: [(&[u8], PfmError); 9] = [
+      (b"P6\n1 1\n255\n", PfmError::UnsupportedFormat),
+      (b"PFx 1 1\n-1.0\n", PfmError::UnsupportedFormat),
+      (b"PF\n1 x\n-1.0\n", PfmError::InvalidHeader { offset: 5 }),
+      (b"PF\n1 1\n0.0\n", PfmError::InvalidHeader { offset: 7 }),
+      (b"PF\n0 1\n-1.0\n", PfmError::ZeroSize),
+      (b"PF\n1 1\n", PfmError::UnexpectedEof),
+      (b"PF\n1 1\n-1.0\n\0\0\0\0", PfmError::UnexpectedEof),
+      // Sizes that overflow on their own, or once the header is added.
+      (b"PF\n4294967296 4294967296\n-1.0\n", PfmError::UnexpectedEof),
+      (b"PF\n1537228672809129301 1\n-1.0\n", PfmError::UnexpectedEof),
+    ];
+
+    for (data, expected) in examples.iter() {
+      assert_eq!(Canvas::from_pfm(data).unwrap_err(), *expected);
+    }
+  }
+}
@@ -0,0 +1,139 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// An infinite plane through the origin on the xz axes.
+#[derive(Debug, Clone)]
+pub struct Plane {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+}
+
+impl Default for Plane {
+  fn default() -> Plane {
+    Plane::new()
+  }
+}
+
+impl Plane {
+  pub fn new() -> Plane {
+    Plane { transform: Transform::identity(), material: Material::new(), parent: None }
+  }
+}
+
+impl Shape for Plane {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    // Rays parallel to the plane, including coplanar ones, never hit it.
+    if ray.direction().y().abs() < float::EPSILON {
+      return vec![];
+    }
+
+    let t = -ray.origin().y() / ray.direction().y();
+    vec![Intersection::new(t, self)]
+  }
+
+  fn local_normal_at(&self, _point: Tuple) -> Tuple {
+    Tuple::vector(0.0, 1.0, 0.0)
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    BoundingBox::new(
+      Tuple::point(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+      Tuple::point(f32::INFINITY, 0.0, f32::INFINITY))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_normal_is_constant() {
+    let p = Plane::new();
+    assert_eq!(p.local_normal_at(Tuple::point(0.0, 0.0, 0.0)), Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(p.local_normal_at(Tuple::point(10.0, 0.0, -10.0)), Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(p.local_normal_at(Tuple::point(-5.0, 0.0, 150.0)), Tuple::vector(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_intersect_parallel() {
+    let p = Plane::new();
+    let r = Ray::new(Tuple::point(0.0, 10.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(p.local_intersect(&r).is_empty());
+  }
+
+  #[test]
+  fn test_intersect_coplanar() {
+    let p = Plane::new();
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    assert!(p.local_intersect(&r).is_empty());
+  }
+
+  #[test]
+  fn test_intersect_from_above() {
+    let p = Plane::new();
+    let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+    let xs = p.local_intersect(&r);
+
+    assert_eq!(xs.len(), 1);
+    assert!(float::eq(xs[0].t(), 1.0));
+    assert!(std::ptr::addr_eq(xs[0].object(), &p));
+  }
+
+  #[test]
+  fn test_intersect_from_below() {
+    let p = Plane::new();
+    let r = Ray::new(Tuple::point(0.0, -1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+    let xs = p.local_intersect(&r);
+
+    assert_eq!(xs.len(), 1);
+    assert!(float::eq(xs[0].t(), 1.0));
+    assert!(std::ptr::addr_eq(xs[0].object(), &p));
+  }
+
+  #[test]
+  fn test_normal_at_transformed() {
+    let mut p = Plane::new();
+    p.set_transform(Mat4::rotation_z(std::f32::consts::PI / 2.0));
+    assert_eq!(p.normal_at(Tuple::point(0.0, 0.0, 0.0)), Tuple::vector(-1.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let b = Plane::new().bounds();
+    assert_eq!(b.min(), Tuple::point(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY));
+    assert_eq!(b.max(), Tuple::point(f32::INFINITY, 0.0, f32::INFINITY));
+  }
+}
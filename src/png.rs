@@ -0,0 +1,246 @@
+use super::canvas::Canvas;
+use super::ppm::quantize;
+use super::zlib;
+use std::io::{self, Write};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PngDepth {
+  Eight,
+  Sixteen,
+}
+
+impl PngDepth {
+  fn bits(self) -> u8 {
+    match self {
+      PngDepth::Eight => 8,
+      PngDepth::Sixteen => 16,
+    }
+  }
+
+  fn bytes_per_pixel(self) -> usize {
+    self.bits() as usize / 8 * 3
+  }
+}
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+// Colour type 2 is RGB without alpha.
+const COLOR_TYPE_RGB: u8 = 2;
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+  let mut crc_input = Vec::with_capacity(data.len() + 4);
+  crc_input.extend_from_slice(kind);
+  crc_input.extend_from_slice(data);
+
+  writer.write_all(&(data.len() as u32).to_be_bytes())?;
+  writer.write_all(&crc_input)?;
+  writer.write_all(&zlib::crc32(&crc_input).to_be_bytes())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+  let p = a as i16 + b as i16 - c as i16;
+  let pa = (p - a as i16).abs();
+  let pb = (p - b as i16).abs();
+  let pc = (p - c as i16).abs();
+  if pa <= pb && pa <= pc {
+    a
+  } else if pb <= pc {
+    b
+  } else {
+    c
+  }
+}
+
+// Applies filter type (0 to 4) to row, given the unfiltered row above it.
+fn filter_row(kind: u8, row: &[u8], above: &[u8], bpp: usize, out: &mut Vec<u8>) {
+  out.push(kind);
+  for i in 0..row.len() {
+    let left = if i >= bpp { row[i - bpp] } else { 0 };
+    let up = above[i];
+    let up_left = if i >= bpp { above[i - bpp] } else { 0 };
+    let predicted = match kind {
+      0 => 0,
+      1 => left,
+      2 => up,
+      3 => ((left as u16 + up as u16) / 2) as u8,
+      _ => paeth(left, up, up_left),
+    };
+    out.push(row[i].wrapping_sub(predicted));
+  }
+}
+
+impl Canvas {
+  // Each row gets whichever filter leaves it with the smallest sum of
+  // absolute differences, the heuristic the PNG spec recommends. Samples are
+  // clamped and rounded the same way as in PPM output.
+  pub fn write_png<W: Write>(&self, mut writer: W, depth: PngDepth) -> io::Result<()> {
+    if self.width() == 0 || self.height() == 0
+      || self.width() > i32::MAX as usize || self.height() > i32::MAX as usize {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "PNG dimensions must be between 1 and 2^31 - 1"));
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(self.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(self.height() as u32).to_be_bytes());
+    // Bit depth, colour type, then default compression, filter and
+    // interlace methods.
+    header.extend_from_slice(&[depth.bits(), COLOR_TYPE_RGB, 0, 0, 0]);
+
+    let bpp = depth.bytes_per_pixel();
+    let stride = self.width() * bpp;
+    let mut filtered = Vec::with_capacity((stride + 1) * self.height());
+    let mut above = vec![0u8; stride];
+    let mut row = Vec::with_capacity(stride);
+    let mut candidate = Vec::with_capacity(stride + 1);
+    let mut best = Vec::with_capacity(stride + 1);
+
+    for y in 0..self.height() {
+      row.clear();
+      for x in 0..self.width() {
+        let pixel = self[(x, y)];
+        for channel in [pixel.red(), pixel.green(), pixel.blue()].iter() {
+          match depth {
+            PngDepth::Eight => row.push(quantize(*channel, 255) as u8),
+            PngDepth::Sixteen => row.extend_from_slice(&quantize(*channel, 65535).to_be_bytes()),
+          }
+        }
+      }
+
+      let mut best_score = u64::MAX;
+      for kind in 0..5 {
+        candidate.clear();
+        filter_row(kind, &row, &above, bpp, &mut candidate);
+        let score = candidate[1..].iter().map(|b| (*b as i8).unsigned_abs() as u64).sum();
+        if score < best_score {
+          best_score = score;
+          std::mem::swap(&mut best, &mut candidate);
+        }
+      }
+      filtered.extend_from_slice(&best);
+      std::mem::swap(&mut above, &mut row);
+    }
+
+    writer.write_all(&SIGNATURE)?;
+    write_chunk(&mut writer, b"IHDR", &header)?;
+    write_chunk(&mut writer, b"IDAT", &zlib::compress(&filtered))?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::color::Color;
+
+  struct Chunk {
+    kind: [u8; 4],
+    data: Vec<u8>,
+  }
+
+  fn chunks(png: &[u8]) -> Vec<Chunk> {
+    assert_eq!(&png[..8], &SIGNATURE);
+    let mut chunks = vec![];
+    let mut pos = 8;
+    while pos < png.len() {
+      let length = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+      let body = &png[pos + 4..pos + 8 + length];
+      let crc = &png[pos + 8 + length..pos + 12 + length];
+      assert_eq!(crc, &zlib::crc32(body).to_be_bytes());
+      chunks.push(Chunk { kind: [body[0], body[1], body[2], body[3]], data: body[4..].to_vec() });
+      pos += 12 + length;
+    }
+    chunks
+  }
+
+  // Reverses the filters, giving back the raw samples.
+  fn unfilter(data: &[u8], stride: usize, bpp: usize) -> Vec<u8> {
+    let mut out: Vec<u8> = vec![];
+    for (y, line) in data.chunks(stride + 1).enumerate() {
+      let start = out.len();
+      for i in 0..stride {
+        let left = if i >= bpp { out[start + i - bpp] } else { 0 };
+        let up = if y > 0 { out[start + i - stride] } else { 0 };
+        let up_left = if y > 0 && i >= bpp { out[start + i - stride - bpp] } else { 0 };
+        let predicted = match line[0] {
+          0 => 0,
+          1 => left,
+          2 => up,
+          3 => ((left as u16 + up as u16) / 2) as u8,
+          4 => paeth(left, up, up_left),
+          kind => panic!("unknown filter {}", kind),
+        };
+        out.push(line[1 + i].wrapping_add(predicted));
+      }
+    }
+    out
+  }
+
+  fn gradient(width: usize, height: usize) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        canvas[(x, y)] = Color::new(x as f32 / width as f32, y as f32 / height as f32, 0.5);
+      }
+    }
+    canvas
+  }
+
+  #[test]
+  fn test_write_png_8_bit() {
+    let canvas = gradient(20, 10);
+    let mut png = Vec::new();
+    canvas.write_png(&mut png, PngDepth::Eight).unwrap();
+
+    let chunks = chunks(&png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|chunk| &chunk.kind).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+    assert_eq!(chunks[0].data, vec![0, 0, 0, 20, 0, 0, 0, 10, 8, 2, 0, 0, 0]);
+    assert!(chunks[2].data.is_empty());
+
+    let samples = unfilter(&zlib::decompress(&chunks[1].data).unwrap(), 60, 3);
+    assert_eq!(samples.len(), 20 * 10 * 3);
+    for y in 0..10 {
+      for x in 0..20 {
+        let pixel = canvas[(x, y)];
+        let i = (y * 20 + x) * 3;
+        assert_eq!(&samples[i..i + 3], &[pixel.red_u8(), pixel.green_u8(), pixel.blue_u8()]);
+      }
+    }
+  }
+
+  #[test]
+  fn test_write_png_16_bit() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas[(0, 0)] = Color::new(1.0, 0.5, 0.0);
+    canvas[(1, 0)] = Color::new(-1.0, 2.0, 0.25);
+    let mut png = Vec::new();
+    canvas.write_png(&mut png, PngDepth::Sixteen).unwrap();
+
+    let chunks = chunks(&png);
+    assert_eq!(chunks[0].data, vec![0, 0, 0, 2, 0, 0, 0, 1, 16, 2, 0, 0, 0]);
+    let samples = unfilter(&zlib::decompress(&chunks[1].data).unwrap(), 12, 6);
+    assert_eq!(samples, vec![
+      0xff, 0xff, 0x80, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0xff, 0xff, 0x40, 0x00,
+    ]);
+  }
+
+  #[test]
+  fn test_write_png_filters_rows() {
+    let canvas = gradient(64, 64);
+    let mut png = Vec::new();
+    canvas.write_png(&mut png, PngDepth::Eight).unwrap();
+
+    let chunks = chunks(&png);
+    let data = zlib::decompress(&chunks[1].data).unwrap();
+    assert!(data.chunks(64 * 3 + 1).any(|line| line[0] != 0));
+    assert!(chunks[1].data.len() < 64 * 64 * 3 / 4);
+  }
+
+  #[test]
+  fn test_write_png_empty_canvas() {
+    let canvas = Canvas::new(0, 3);
+    let err = canvas.write_png(Vec::new(), PngDepth::Eight).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+  }
+}
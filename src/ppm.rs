@@ -0,0 +1,521 @@
+use super::color::Color;
+use super::canvas::Canvas;
+use std::fmt;
+use std::io::{self, Write};
+
+// Plain PPM (P3) is ASCII text, which is easy to inspect but around four
+// times the size of raw PPM (P6), which stores each sample in binary.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PpmFormat {
+  Plain,
+  Raw,
+}
+
+// Lines in plain PPM files shouldn't be longer than this.
+const MAX_LINE_LENGTH: usize = 70;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PpmOptions {
+  format: PpmFormat,
+  maxval: u16,
+  comments: Vec<String>,
+}
+
+impl PpmOptions {
+  pub fn new(format: PpmFormat) -> PpmOptions {
+    PpmOptions { format, maxval: 255, comments: vec![] }
+  }
+
+  pub fn format(&self) -> PpmFormat {
+    self.format
+  }
+
+  pub fn maxval(&self) -> u16 {
+    self.maxval
+  }
+
+  pub fn comments(&self) -> &[String] {
+    &self.comments
+  }
+
+  pub fn set_format(&mut self, format: PpmFormat) {
+    self.format = format;
+  }
+
+  // Anything above 255 stores raw samples in two bytes, so 65535 gives 16-bit
+  // output.
+  pub fn set_maxval(&mut self, maxval: u16) {
+    assert!(maxval > 0, "maxval must be at least 1");
+    self.maxval = maxval;
+  }
+
+  // Written into the header as # comments, one per line of text.
+  pub fn add_comment(&mut self, comment: impl Into<String>) {
+    self.comments.push(comment.into());
+  }
+}
+
+// The same rounding as Color's u8 conversion, at any maxval.
+pub fn quantize(n: f32, maxval: u16) -> u16 {
+  let r = n * maxval as f32;
+  if r > maxval as f32 {
+    maxval
+  } else if r <= 0.0 {
+    0
+  } else {
+    r.round() as u16
+  }
+}
+
+fn push_decimal(buf: &mut Vec<u8>, n: u16) {
+  let mut digits = [0u8; 5];
+  let mut len = 0;
+  let mut n = n;
+  loop {
+    digits[len] = b'0' + (n % 10) as u8;
+    len += 1;
+    n /= 10;
+    if n == 0 {
+      break;
+    }
+  }
+  buf.extend(digits[..len].iter().rev());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PpmError {
+  // The file doesn't start with P3 or P6.
+  UnsupportedFormat,
+  // A header field or plain sample that isn't a number.
+  InvalidNumber { offset: usize },
+  InvalidMaxval(u32),
+  ZeroSize,
+  SampleOutOfRange { offset: usize, value: u32, maxval: u32 },
+  UnexpectedEof,
+}
+
+impl fmt::Display for PpmError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PpmError::UnsupportedFormat => write!(f, "not a P3 or P6 PPM file"),
+      PpmError::InvalidNumber { offset } => write!(f, "expected a number at byte {}", offset),
+      PpmError::InvalidMaxval(maxval) => write!(f, "maxval {} is not between 1 and 65535", maxval),
+      PpmError::ZeroSize => write!(f, "image has no pixels"),
+      PpmError::SampleOutOfRange { offset, value, maxval } =>
+        write!(f, "sample {} at byte {} is greater than maxval {}", value, offset, maxval),
+      PpmError::UnexpectedEof => write!(f, "file ends before the image does"),
+    }
+  }
+}
+
+impl std::error::Error for PpmError {}
+
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  // Whitespace and comments can appear between any two tokens.
+  fn skip_whitespace(&mut self) {
+    while let Some(&c) = self.data.get(self.pos) {
+      if c == b'#' {
+        while self.pos < self.data.len() && self.data[self.pos] != b'\n' && self.data[self.pos] != b'\r' {
+          self.pos += 1;
+        }
+      } else if c.is_ascii_whitespace() {
+        self.pos += 1;
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn number(&mut self) -> Result<u32, PpmError> {
+    self.skip_whitespace();
+    let start = self.pos;
+    let mut value: u32 = 0;
+
+    while let Some(&c) = self.data.get(self.pos) {
+      if !c.is_ascii_digit() {
+        break;
+      }
+      value = value.checked_mul(10)
+        .and_then(|v| v.checked_add((c - b'0') as u32))
+        .ok_or(PpmError::InvalidNumber { offset: start })?;
+      self.pos += 1;
+    }
+
+    if self.pos == start {
+      return Err(match self.data.get(self.pos) {
+        Some(_) => PpmError::InvalidNumber { offset: start },
+        None => PpmError::UnexpectedEof,
+      });
+    }
+    Ok(value)
+  }
+}
+
+impl Canvas {
+  // Streams the image a row at a time, so only one row is held in memory.
+  // Plain rows are wrapped so no line goes over 70 characters, and each row
+  // of pixels starts on a new line.
+  pub fn write_ppm<W: Write>(&self, mut writer: W, options: &PpmOptions) -> io::Result<()> {
+    let magic = match options.format {
+      PpmFormat::Plain => "P3",
+      PpmFormat::Raw => "P6",
+    };
+    writeln!(writer, "{}", magic)?;
+    for comment in options.comments.iter() {
+      for line in comment.lines() {
+        writeln!(writer, "# {}", line)?;
+      }
+    }
+    write!(writer, "{} {}\n{}\n", self.width(), self.height(), options.maxval)?;
+
+    let maxval = options.maxval;
+    let wide = maxval > 255;
+    let mut row = Vec::with_capacity(self.width() * 12);
+    let mut sample = Vec::with_capacity(5);
+
+    for y in 0..self.height() {
+      row.clear();
+      let mut line_start = 0;
+
+      for x in 0..self.width() {
+        let pixel = self[(x, y)];
+        for channel in [pixel.red(), pixel.green(), pixel.blue()].iter() {
+          let value = quantize(*channel, maxval);
+
+          match options.format {
+            PpmFormat::Raw if wide => row.extend_from_slice(&value.to_be_bytes()),
+            PpmFormat::Raw => row.push(value as u8),
+            PpmFormat::Plain => {
+              sample.clear();
+              push_decimal(&mut sample, value);
+
+              if row.len() > line_start {
+                if row.len() - line_start + 1 + sample.len() > MAX_LINE_LENGTH {
+                  row.push(b'\n');
+                  line_start = row.len();
+                } else {
+                  row.push(b' ');
+                }
+              }
+              row.extend_from_slice(&sample);
+            },
+          }
+        }
+      }
+
+      if options.format == PpmFormat::Plain {
+        row.push(b'\n');
+      }
+      writer.write_all(&row)?;
+    }
+
+    writer.flush()
+  }
+
+  // Reads plain (P3) and raw (P6) PPM at any maxval, scaling samples to 0..1.
+  pub fn from_ppm(data: &[u8]) -> Result<Canvas, PpmError> {
+    let raw = match data.get(..2) {
+      Some(b"P3") => false,
+      Some(b"P6") => true,
+      _ => return Err(PpmError::UnsupportedFormat),
+    };
+    if !data.get(2).is_some_and(|c| c.is_ascii_whitespace()) {
+      return Err(PpmError::UnsupportedFormat);
+    }
+
+    let mut reader = Reader { data, pos: 2 };
+    let width = reader.number()? as usize;
+    let height = reader.number()? as usize;
+    let maxval = reader.number()?;
+
+    if !(1..=65535).contains(&maxval) {
+      return Err(PpmError::InvalidMaxval(maxval));
+    }
+    if width == 0 || height == 0 {
+      return Err(PpmError::ZeroSize);
+    }
+
+    let scale = maxval as f32;
+    // A size too big to count can't be in the file either.
+    let sample_count = width.checked_mul(height)
+      .and_then(|n| n.checked_mul(3))
+      .ok_or(PpmError::UnexpectedEof)?;
+
+    // The canvas is only allocated once the file is known to be big enough
+    // for it, so a bogus header can't ask for gigabytes.
+    let mut canvas;
+
+    if raw {
+      // Exactly one whitespace byte separates the header from the raster.
+      match data.get(reader.pos) {
+        Some(c) if c.is_ascii_whitespace() => reader.pos += 1,
+        Some(_) => return Err(PpmError::InvalidNumber { offset: reader.pos }),
+        None => return Err(PpmError::UnexpectedEof),
+      }
+
+      let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+      let len = sample_count.checked_mul(bytes_per_sample).ok_or(PpmError::UnexpectedEof)?;
+      let raster = data.get(reader.pos..).and_then(|rest| rest.get(..len)).ok_or(PpmError::UnexpectedEof)?;
+      canvas = Canvas::new(width, height);
+
+      for (i, pixel) in raster.chunks_exact(3 * bytes_per_sample).enumerate() {
+        let mut samples = [0.0; 3];
+        for (c, sample) in samples.iter_mut().enumerate() {
+          let offset = c * bytes_per_sample;
+          let value = if bytes_per_sample == 1 {
+            pixel[offset] as u32
+          } else {
+            u16::from_be_bytes([pixel[offset], pixel[offset + 1]]) as u32
+          };
+          if value > maxval {
+            let offset = reader.pos + i * 3 * bytes_per_sample + offset;
+            return Err(PpmError::SampleOutOfRange { offset, value, maxval });
+          }
+          *sample = value as f32 / scale;
+        }
+        canvas[(i % width, i / width)] = Color::new(samples[0], samples[1], samples[2]);
+      }
+    } else {
+      // Every sample but the last takes at least a digit and a separator.
+      if sample_count > data.len() - reader.pos {
+        return Err(PpmError::UnexpectedEof);
+      }
+      canvas = Canvas::new(width, height);
+
+      for y in 0..height {
+        for x in 0..width {
+          let mut samples = [0.0; 3];
+          for sample in samples.iter_mut() {
+            reader.skip_whitespace();
+            let offset = reader.pos;
+            let value = reader.number()?;
+            if value > maxval {
+              return Err(PpmError::SampleOutOfRange { offset, value, maxval });
+            }
+            *sample = value as f32 / scale;
+          }
+          canvas[(x, y)] = Color::new(samples[0], samples[1], samples[2]);
+        }
+      }
+    }
+
+    Ok(canvas)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_write_ppm_raw() {
+    let mut canvas = Canvas::new(2, 2);
+    canvas[(0, 0)] = Color::new(1.5, 0.0, 0.0);
+    canvas[(1, 0)] = Color::new(0.0, 0.5, 0.0);
+    canvas[(1, 1)] = Color::new(-0.5, 0.0, 1.0);
+
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, &PpmOptions::new(PpmFormat::Raw)).unwrap();
+
+    let header = b"P6\n2 2\n255\n";
+    assert_eq!(&buf[..header.len()], header);
+    assert_eq!(&buf[header.len()..], &[
+      255, 0, 0, 0, 128, 0,
+      0, 0, 0, 0, 0, 255,
+    ]);
+  }
+
+  #[test]
+  fn test_write_ppm_plain_matches_to_ppm() {
+    let mut canvas = Canvas::new(4, 3);
+    canvas[(1, 2)] = Color::new(0.04, 0.4, 1.0);
+
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, &PpmOptions::new(PpmFormat::Plain)).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), canvas.to_ppm());
+    assert!(canvas.to_ppm().contains("0 0 0 10 102 255 0 0 0"));
+  }
+
+  #[test]
+  fn test_write_ppm_wraps_plain_lines() {
+    let mut canvas = Canvas::new(30, 2);
+    for x in 0..30 {
+      canvas[(x, 0)] = Color::new(1.0, 0.8, 0.6);
+    }
+    let mut options = PpmOptions::new(PpmFormat::Plain);
+    options.set_maxval(65535);
+
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, &options).unwrap();
+    let ppm = String::from_utf8(buf).unwrap();
+
+    assert!(ppm.lines().all(|line| line.len() <= 70));
+    assert!(ppm.lines().skip(3).all(|line| !line.starts_with(' ') && !line.ends_with(' ')));
+    assert_eq!(ppm.lines().nth(3).unwrap(), "65535 52428 39321 65535 52428 39321 65535 52428 39321 65535 52428");
+  }
+
+  #[test]
+  fn test_write_ppm_raw_16_bit() {
+    let mut canvas = Canvas::new(1, 1);
+    canvas[(0, 0)] = Color::new(1.0, 0.5, 0.0);
+    let mut options = PpmOptions::new(PpmFormat::Raw);
+    options.set_maxval(65535);
+
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, &options).unwrap();
+
+    let header = b"P6\n1 1\n65535\n";
+    assert_eq!(&buf[..header.len()], header);
+    assert_eq!(&buf[header.len()..], &[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+  }
+
+  #[test]
+  fn test_write_ppm_comments() {
+    let canvas = Canvas::new(1, 1);
+    let mut options = PpmOptions::new(PpmFormat::Plain);
+    options.add_comment("rendered in 1.5s");
+    options.add_comment("scene: a\nscene: b");
+
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, &options).unwrap();
+    let ppm = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = ppm.lines().collect();
+
+    assert_eq!(lines, vec!["P3", "# rendered in 1.5s", "# scene: a", "# scene: b", "1 1", "255", "0 0 0"]);
+    assert_eq!(Canvas::from_ppm(ppm.as_bytes()).unwrap().width(), 1);
+  }
+
+  #[test]
+  fn test_magic_number() {
+    assert_eq!(Canvas::from_ppm(b"P32\n1 1\n255\n0 0 0\n").unwrap_err(), PpmError::UnsupportedFormat);
+    assert_eq!(Canvas::from_ppm(b"").unwrap_err(), PpmError::UnsupportedFormat);
+  }
+
+  #[test]
+  fn test_plain() {
+    let ppm = b"P3\n10 2\n15\n\
+                0 0 0  1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9\n\
+                1 1 1  2 2 2  3 3 3  4 4 4  5 5 5  6 6 6  7 7 7  8 8 8  9 9 9  0 0 0\n";
+    let canvas = Canvas::from_ppm(ppm).unwrap();
+
+    assert_eq!(canvas.width(), 10);
+    assert_eq!(canvas.height(), 2);
+    assert_eq!(canvas[(0, 0)], Color::new(0.0, 0.0, 0.0));
+    assert_eq!(canvas[(3, 0)], Color::new(0.2, 0.2, 0.2));
+    assert_eq!(canvas[(9, 1)], Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_comments_and_whitespace() {
+    let ppm = b"P3\n# this is a comment\n2 1\n# this, too\n255\n\
+                # another comment\n255 255 255\n# oh, no, comments in the pixel data!\n255 0 255\n";
+    let canvas = Canvas::from_ppm(ppm).unwrap();
+
+    assert_eq!(canvas[(0, 0)], Color::new(1.0, 1.0, 1.0));
+    assert_eq!(canvas[(1, 0)], Color::new(1.0, 0.0, 1.0));
+  }
+
+  #[test]
+  fn test_samples_across_lines() {
+    let ppm = b"P3\n1 1\n255\n51\n153\n\n204\n";
+    let canvas = Canvas::from_ppm(ppm).unwrap();
+
+    assert_eq!(canvas[(0, 0)], Color::new(0.2, 0.6, 0.8));
+  }
+
+  #[test]
+  fn test_maxval_scaling() {
+    let ppm = b"P3 2 2 100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+    let canvas = Canvas::from_ppm(ppm).unwrap();
+
+    assert_eq!(canvas[(0, 1)], Color::new(0.75, 0.5, 0.25));
+  }
+
+  #[test]
+  fn test_raw() {
+    let mut ppm = b"P6\n# comment\n2 1\n255\n".to_vec();
+    ppm.extend_from_slice(&[255, 0, 51, 0, 255, 0]);
+    let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+    assert_eq!(canvas[(0, 0)], Color::new(1.0, 0.0, 0.2));
+    assert_eq!(canvas[(1, 0)], Color::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_raw_16_bit() {
+    let mut ppm = b"P6 1 1 65535\n".to_vec();
+    ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+    assert_eq!(canvas[(0, 0)], Color::new(1.0, 0.50001, 0.0));
+  }
+
+  fn round_trip(canvas: &Canvas, options: &PpmOptions) -> Canvas {
+    let mut buf = Vec::new();
+    canvas.write_ppm(&mut buf, options).unwrap();
+    Canvas::from_ppm(&buf).unwrap()
+  }
+
+  #[test]
+  fn test_round_trip_8_bit() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas[(0, 0)] = Color::new(1.0, 0.2, 0.0);
+    canvas[(2, 1)] = Color::new(0.4, 0.6, 0.8);
+
+    for format in [PpmFormat::Plain, PpmFormat::Raw].iter() {
+      let read = round_trip(&canvas, &PpmOptions::new(*format));
+
+      assert_eq!(read[(0, 0)], canvas[(0, 0)]);
+      assert_eq!(read[(2, 1)], canvas[(2, 1)]);
+    }
+  }
+
+  #[test]
+  fn test_round_trip_16_bit() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas[(0, 0)] = Color::new(1.0, 0.2, 0.0);
+    canvas[(2, 1)] = Color::new(0.123456, 0.654321, 0.999);
+
+    for format in [PpmFormat::Plain, PpmFormat::Raw].iter() {
+      let mut options = PpmOptions::new(*format);
+      options.set_maxval(65535);
+      let read = round_trip(&canvas, &options);
+
+      assert_eq!(read[(0, 0)], canvas[(0, 0)]);
+      // Too fine for 8 bits, which would be off by up to 1/510.
+      let (a, b) = (read[(2, 1)], canvas[(2, 1)]);
+      assert!((a.red() - b.red()).abs() < 1e-4);
+      assert!((a.green() - b.green()).abs() < 1e-4);
+      assert!((a.blue() - b.blue()).abs() < 1e-4);
+    }
+  }
+
+  #[test]
+  fn test_huge_headers() {
+    assert_eq!(Canvas::from_ppm(b"P3 60000 60000 255\n0").unwrap_err(), PpmError::UnexpectedEof);
+    assert_eq!(Canvas::from_ppm(b"P6 4294967295 4294967295 255\n").unwrap_err(), PpmError::UnexpectedEof);
+    assert_eq!(Canvas::from_ppm(b"P6 60000 60000 65535\n\0\0").unwrap_err(), PpmError::UnexpectedEof);
+  }
+
+  #[test]
+  fn test_errors() {
+    let examples: [(&[u8], PpmError); 7] = [
+      (b"P3\n1 x\n255\n", PpmError::InvalidNumber { offset: 5 }),
+      (b"P3\n1 1\n0\n", PpmError::InvalidMaxval(0)),
+      (b"P3\n1 1\n70000\n", PpmError::InvalidMaxval(70000)),
+      (b"P3\n0 1\n255\n", PpmError::ZeroSize),
+      (b"P3\n1 1\n255\n0 0", PpmError::UnexpectedEof),
+      (b"P3\n1 1\n15\n0 16 0\n", PpmError::SampleOutOfRange { offset: 12, value: 16, maxval: 15 }),
+      (b"P6\n2 1\n255\n\x00\x00\x00", PpmError::UnexpectedEof),
+    ];
+
+    for (ppm, err) in examples.iter() {
+      assert_eq!(Canvas::from_ppm(ppm).unwrap_err(), *err);
+    }
+  }
+}
@@ -1,4 +1,5 @@
 use super::tuple::Tuple;
+use super::matrix::Mat4;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Ray {
@@ -30,6 +31,10 @@ impl Ray {
   pub fn position_at_time(&self, t: f32) -> Tuple {
     self.origin + (self.direction * t)
   }
+
+  pub fn transform(&self, m: &Mat4) -> Ray {
+    Ray { origin: *m * self.origin, direction: *m * self.direction }
+  }
 }
 
 #[cfg(test)]
@@ -46,7 +51,25 @@ mod tests {
   #[test]
   fn test_position_at_time() {
     let ray = Ray::new(Tuple::point(2.0, 3.0, 4.0), Tuple::vector(1.0, 0.0, 0.0));
-    assert_eq!(ray.origin(), Tuple::point(1.0, 2.0, 3.0));
-    assert_eq!(ray.direction(), Tuple::vector(4.0, 5.0, 6.0));
+    assert_eq!(ray.position_at_time(0.0), Tuple::point(2.0, 3.0, 4.0));
+    assert_eq!(ray.position_at_time(1.0), Tuple::point(3.0, 3.0, 4.0));
+    assert_eq!(ray.position_at_time(-1.0), Tuple::point(1.0, 3.0, 4.0));
+    assert_eq!(ray.position_at_time(2.5), Tuple::point(4.5, 3.0, 4.0));
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_translate() {
+    let ray = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
+    let r2 = ray.transform(&Mat4::translation(3.0, 4.0, 5.0));
+    assert_eq!(r2.origin(), Tuple::point(4.0, 6.0, 8.0));
+    assert_eq!(r2.direction(), Tuple::vector(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_scale() {
+    let ray = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
+    let r2 = ray.transform(&Mat4::scaling(2.0, 3.0, 4.0));
+    assert_eq!(r2.origin(), Tuple::point(2.0, 6.0, 12.0));
+    assert_eq!(r2.direction(), Tuple::vector(0.0, 3.0, 0.0));
+  }
+}
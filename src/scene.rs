@@ -0,0 +1,718 @@
+use super::tuple::Tuple;
+use super::color::Color;
+use super::matrix::Mat4;
+use super::material::Material;
+use super::pattern::{Pattern, StripePattern, GradientPattern, RingPattern, CheckersPattern};
+use super::light::PointLight;
+use super::shape::Shape;
+use super::sphere::Sphere;
+use super::plane::Plane;
+use super::cube::Cube;
+use super::cylinder::Cylinder;
+use super::cone::Cone;
+use super::group::Group;
+use super::triangle::Triangle;
+use super::csg::{Csg, Operation};
+use super::obj::ObjFile;
+use super::world::World;
+use super::camera::Camera;
+use super::yaml::{self, Node, Value, YamlError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum SceneError {
+  Io(io::Error),
+  Invalid { line: usize, message: String },
+}
+
+impl SceneError {
+  pub fn line(&self) -> Option<usize> {
+    match self {
+      SceneError::Io(_) => None,
+      SceneError::Invalid { line, .. } => Some(*line),
+    }
+  }
+}
+
+impl fmt::Display for SceneError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SceneError::Io(err) => write!(f, "{}", err),
+      SceneError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+    }
+  }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+  fn from(err: io::Error) -> SceneError {
+    SceneError::Io(err)
+  }
+}
+
+impl From<YamlError> for SceneError {
+  fn from(err: YamlError) -> SceneError {
+    SceneError::Invalid { line: err.line(), message: err.message().to_string() }
+  }
+}
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+fn invalid<T>(node: &Node, message: impl Into<String>) -> Result<T> {
+  Err(node.error(message).into())
+}
+
+// A world and the camera to render it with, as described by a scene file.
+//
+// Scene files are a list of items. `add: camera` and `add: light` set up the
+// view and lighting, `add: <shape>` adds a shape, and `define: <name>` gives a
+// name to a material, a transform list or a shape that later items can use
+// in its place. A definition can `extend` another to override some of its
+// keys. Transforms are lists like `[translate, 1, 2, 3]`, applied in order.
+#[derive(Debug)]
+pub struct Scene {
+  world: World,
+  camera: Camera,
+}
+
+impl Scene {
+  pub fn parse(input: &str) -> Result<Scene> {
+    Loader::new(None).load(input)
+  }
+
+  // OBJ files referred to by the scene are looked for relative to it.
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Scene> {
+    let path = path.as_ref();
+    let input = fs::read_to_string(path)?;
+    Loader::new(path.parent().map(Path::to_path_buf)).load(&input)
+  }
+
+  pub fn world(&self) -> &World {
+    &self.world
+  }
+
+  pub fn camera(&self) -> &Camera {
+    &self.camera
+  }
+
+  pub fn into_parts(self) -> (World, Camera) {
+    (self.world, self.camera)
+  }
+}
+
+struct Loader {
+  base_dir: Option<PathBuf>,
+  defines: HashMap<String, Node>,
+  // Names of the definitions being expanded right now, innermost last.
+  expanding: RefCell<Vec<String>>,
+}
+
+impl Loader {
+  fn new(base_dir: Option<PathBuf>) -> Loader {
+    Loader { base_dir, defines: HashMap::new(), expanding: RefCell::new(vec![]) }
+  }
+
+  fn load(&mut self, input: &str) -> Result<Scene> {
+    let doc = yaml::parse(input)?;
+    let mut world = World::new();
+    let mut camera = None;
+
+    for item in doc.as_sequence()? {
+      if let Some(name) = item.get("define") {
+        self.define(item, name)?;
+        continue;
+      }
+
+      let kind = match item.get("add") {
+        Some(kind) => kind,
+        None => return invalid(item, "expected an add or define item"),
+      };
+
+      match kind.as_str()? {
+        "camera" => camera = Some(self.camera(item)?),
+        "light" => world.add_light(self.light(item)?),
+        _ => world.add_object(self.shape(item)?),
+      }
+    }
+
+    match camera {
+      Some(camera) => Ok(Scene { world, camera }),
+      None => invalid(&doc, "the scene has no camera"),
+    }
+  }
+
+  fn define(&mut self, item: &Node, name: &Node) -> Result<()> {
+    check_keys(item, &["define", "extend", "value"])?;
+    let value = required(item, "value")?;
+
+    let value = match item.get("extend") {
+      Some(base) => {
+        let base_value = self.lookup(base)?;
+        let mut entries = base_value.as_mapping()?.to_vec();
+        for (key, node) in value.as_mapping()? {
+          entries.retain(|(k, _)| k != key);
+          entries.push((key.clone(), node.clone()));
+        }
+        Node::new(value.line(), Value::Mapping(entries))
+      },
+      None => value.clone(),
+    };
+
+    self.defines.insert(name.as_str()?.to_string(), value);
+    Ok(())
+  }
+
+  fn lookup(&self, name: &Node) -> Result<&Node> {
+    let key = name.as_str()?;
+    match self.defines.get(key) {
+      Some(node) => Ok(node),
+      None => invalid(name, format!("{:?} hasn't been defined", key)),
+    }
+  }
+
+  // Expands a defined name with f, refusing any name that's already being
+  // expanded further out, which would otherwise recurse forever.
+  fn expand<T>(&self, name: &Node, f: impl FnOnce(&Node) -> Result<T>) -> Result<T> {
+    let key = name.as_str()?;
+    if self.expanding.borrow().iter().any(|n| n == key) {
+      return invalid(name, format!("{:?} is defined in terms of itself", key));
+    }
+
+    let value = self.lookup(name)?;
+    self.expanding.borrow_mut().push(key.to_string());
+    let result = f(value);
+    self.expanding.borrow_mut().pop();
+    result
+  }
+
+  fn camera(&self, item: &Node) -> Result<Camera> {
+    check_keys(item, &["add", "width", "height", "field-of-view", "from", "to", "up"])?;
+    let width = to_size(required(item, "width")?)?;
+    let height = to_size(required(item, "height")?)?;
+    let fov = required(item, "field-of-view")?.as_f32()?;
+
+    let mut camera = Camera::new(width, height, fov);
+    camera.set_transform(Mat4::view_transform(
+      to_point(required(item, "from")?)?,
+      to_point(required(item, "to")?)?,
+      to_vector(required(item, "up")?)?));
+    Ok(camera)
+  }
+
+  fn light(&self, item: &Node) -> Result<PointLight> {
+    check_keys(item, &["add", "at", "intensity"])?;
+    Ok(PointLight::new(to_point(required(item, "at")?)?, to_color(required(item, "intensity")?)?))
+  }
+
+  fn shape(&self, item: &Node) -> Result<Box<dyn Shape>> {
+    let kind = required(item, "add")?;
+
+    let mut shape: Box<dyn Shape> = match kind.as_str()? {
+      "sphere" => {
+        check_keys(item, &["add", "material", "transform"])?;
+        Box::new(Sphere::new())
+      },
+      "plane" => {
+        check_keys(item, &["add", "material", "transform"])?;
+        Box::new(Plane::new())
+      },
+      "cube" => {
+        check_keys(item, &["add", "material", "transform"])?;
+        Box::new(Cube::new())
+      },
+      "cylinder" => {
+        check_keys(item, &["add", "material", "transform", "min", "max", "closed"])?;
+        let mut cyl = Cylinder::new();
+        if let Some(min) = item.get("min") {
+          cyl.set_minimum(min.as_f32()?);
+        }
+        if let Some(max) = item.get("max") {
+          cyl.set_maximum(max.as_f32()?);
+        }
+        if let Some(closed) = item.get("closed") {
+          cyl.set_closed(closed.as_bool()?);
+        }
+        Box::new(cyl)
+      },
+      "cone" => {
+        check_keys(item, &["add", "material", "transform", "min", "max", "closed"])?;
+        let mut cone = Cone::new();
+        if let Some(min) = item.get("min") {
+          cone.set_minimum(min.as_f32()?);
+        }
+        if let Some(max) = item.get("max") {
+          cone.set_maximum(max.as_f32()?);
+        }
+        if let Some(closed) = item.get("closed") {
+          cone.set_closed(closed.as_bool()?);
+        }
+        Box::new(cone)
+      },
+      "triangle" => {
+        check_keys(item, &["add", "material", "transform", "p1", "p2", "p3"])?;
+        Box::new(Triangle::new(
+          to_point(required(item, "p1")?)?,
+          to_point(required(item, "p2")?)?,
+          to_point(required(item, "p3")?)?))
+      },
+      "group" => {
+        check_keys(item, &["add", "material", "transform", "children", "divide"])?;
+        let mut group = Group::new();
+        for child in required(item, "children")?.as_sequence()? {
+          group.add_child(self.shape(child)?);
+        }
+        Box::new(group)
+      },
+      "obj" => {
+        check_keys(item, &["add", "material", "transform", "file", "divide"])?;
+        Box::new(self.obj(required(item, "file")?)?)
+      },
+      "csg" => {
+        check_keys(item, &["add", "material", "transform", "operation", "left", "right"])?;
+        let op = required(item, "operation")?;
+        let operation = match op.as_str()? {
+          "union" => Operation::Union,
+          "intersection" => Operation::Intersection,
+          "difference" => Operation::Difference,
+          other => return invalid(op, format!("unknown CSG operation {:?}", other)),
+        };
+        let left = self.shape(required(item, "left")?)?;
+        let right = self.shape(required(item, "right")?)?;
+        Box::new(Csg::new(operation, left, right))
+      },
+      // Anything else has to be a defined shape, with this item's keys laid
+      // over the definition's.
+      _ => return self.expand(kind, |base| {
+        let mut entries = base.as_mapping()?.to_vec();
+        for (key, node) in item.as_mapping()? {
+          if key != "add" {
+            entries.retain(|(k, _)| k != key);
+            entries.push((key.clone(), node.clone()));
+          }
+        }
+        self.shape(&Node::new(item.line(), Value::Mapping(entries)))
+      }),
+    };
+
+    if let Some(material) = item.get("material") {
+      shape.set_material(self.material(material)?);
+    }
+    if let Some(transform) = item.get("transform") {
+      shape.set_transform(self.transform(transform)?);
+    }
+    if let Some(threshold) = item.get("divide") {
+      shape.divide(to_usize(threshold)?);
+    }
+    Ok(shape)
+  }
+
+  fn obj(&self, file: &Node) -> Result<Group> {
+    let path = match &self.base_dir {
+      Some(dir) => dir.join(file.as_str()?),
+      None => PathBuf::from(file.as_str()?),
+    };
+
+    match ObjFile::from_file(&path) {
+      Ok(obj) => Ok(obj.into_group()),
+      Err(err) => invalid(file, format!("couldn't read {}: {}", path.display(), err)),
+    }
+  }
+
+  fn material(&self, node: &Node) -> Result<Material> {
+    let node = match node.value() {
+      Value::Scalar(_) => self.lookup(node)?,
+      _ => node,
+    };
+    check_keys(node, &[
+      "color", "pattern", "ambient", "diffuse", "specular", "shininess",
+      "reflective", "transparency", "refractive-index",
+    ])?;
+
+    let mut material = Material::new();
+    for (key, value) in node.as_mapping()? {
+      match key.as_str() {
+        "color" => material.set_color(to_color(value)?),
+        "pattern" => material.set_pattern(self.pattern(value)?),
+        "ambient" => material.set_ambient(value.as_f32()?),
+        "diffuse" => material.set_diffuse(value.as_f32()?),
+        "specular" => material.set_specular(value.as_f32()?),
+        "shininess" => material.set_shininess(value.as_f32()?),
+        "reflective" => material.set_reflective(value.as_f32()?),
+        "transparency" => material.set_transparency(value.as_f32()?),
+        _ => material.set_refractive_index(value.as_f32()?),
+      }
+    }
+    Ok(material)
+  }
+
+  fn pattern(&self, node: &Node) -> Result<Box<dyn Pattern>> {
+    check_keys(node, &["type", "colors", "transform"])?;
+    let colors = required(node, "colors")?;
+    let (a, b) = match colors.as_sequence()? {
+      [a, b] => (to_color(a)?, to_color(b)?),
+      _ => return invalid(colors, "expected two colors"),
+    };
+
+    let kind = required(node, "type")?;
+    let mut pattern: Box<dyn Pattern> = match kind.as_str()? {
+      "stripes" => Box::new(StripePattern::new(a, b)),
+      "gradient" => Box::new(GradientPattern::new(a, b)),
+      "rings" => Box::new(RingPattern::new(a, b)),
+      "checkers" => Box::new(CheckersPattern::new(a, b)),
+      other => return invalid(kind, format!("unknown pattern {:?}", other)),
+    };
+
+    if let Some(transform) = node.get("transform") {
+      pattern.set_transform(self.transform(transform)?);
+    }
+    Ok(pattern)
+  }
+
+  // Shapes and patterns only ever use the inverse, so a transform that
+  // flattens them to nothing is refused rather than filling renders with NaN.
+  fn transform(&self, node: &Node) -> Result<Mat4> {
+    let m = self.steps(node)?;
+    if !m.is_invertible() {
+      return invalid(node, "transform can't be inverted");
+    }
+    Ok(m)
+  }
+
+  // Each step is applied after the ones before it. Named steps are expanded
+  // in place, so definitions can build on each other.
+  fn steps(&self, node: &Node) -> Result<Mat4> {
+    let mut m = Mat4::identity();
+
+    for step in node.as_sequence()? {
+      if let Value::Scalar(_) = step.value() {
+        m = self.expand(step, |value| self.steps(value))? * m;
+        continue;
+      }
+
+      let parts = step.as_sequence()?;
+      let (op, args) = match parts.split_first() {
+        Some((op, args)) => (op, args.iter().map(Node::as_f32).collect::<std::result::Result<Vec<f32>, _>>()?),
+        None => return invalid(step, "empty transform"),
+      };
+
+      m = match (op.as_str()?, args.as_slice()) {
+        ("translate", [x, y, z]) => m.translate(*x, *y, *z),
+        ("scale", [x, y, z]) => m.scale(*x, *y, *z),
+        ("rotate-x", [r]) => m.rotate_x(*r),
+        ("rotate-y", [r]) => m.rotate_y(*r),
+        ("rotate-z", [r]) => m.rotate_z(*r),
+        ("shear", [xy, xz, yx, yz, zx, zy]) => m.shear(*xy, *xz, *yx, *yz, *zx, *zy),
+        ("translate", _) | ("scale", _) => return invalid(step, "expected 3 numbers"),
+        ("rotate-x", _) | ("rotate-y", _) | ("rotate-z", _) => return invalid(step, "expected 1 number"),
+        ("shear", _) => return invalid(step, "expected 6 numbers"),
+        (other, _) => return invalid(op, format!("unknown transform {:?}", other)),
+      };
+    }
+
+    Ok(m)
+  }
+}
+
+fn required<'a>(item: &'a Node, key: &str) -> Result<&'a Node> {
+  match item.get(key) {
+    Some(node) => Ok(node),
+    None => invalid(item, format!("missing {:?}", key)),
+  }
+}
+
+// Catches typos, which would otherwise be silently ignored.
+fn check_keys(item: &Node, allowed: &[&str]) -> Result<()> {
+  for (key, value) in item.as_mapping()? {
+    if !allowed.contains(&key.as_str()) {
+      return invalid(value, format!("unexpected key {:?}", key));
+    }
+  }
+  Ok(())
+}
+
+fn to_usize(node: &Node) -> Result<usize> {
+  let s = node.as_str()?;
+  s.parse().or_else(|_| invalid(node, format!("expected a whole number, found {:?}", s)))
+}
+
+// Image dimensions, where an empty image would only fail once it's written.
+fn to_size(node: &Node) -> Result<usize> {
+  match to_usize(node)? {
+    0 => invalid(node, "expected at least 1 pixel"),
+    n => Ok(n),
+  }
+}
+
+fn to_triple(node: &Node) -> Result<(f32, f32, f32)> {
+  match node.as_sequence()? {
+    [x, y, z] => Ok((x.as_f32()?, y.as_f32()?, z.as_f32()?)),
+    _ => invalid(node, "expected 3 numbers"),
+  }
+}
+
+fn to_point(node: &Node) -> Result<Tuple> {
+  let (x, y, z) = to_triple(node)?;
+  Ok(Tuple::point(x, y, z))
+}
+
+fn to_vector(node: &Node) -> Result<Tuple> {
+  let (x, y, z) = to_triple(node)?;
+  Ok(Tuple::vector(x, y, z))
+}
+
+fn to_color(node: &Node) -> Result<Color> {
+  let (r, g, b) = to_triple(node)?;
+  Ok(Color::new(r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+  use super::super::ray::Ray;
+  use std::f32::consts::PI;
+
+  const CAMERA: &str = "\
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [ 0, 1.5, -5 ]
+  to: [ 0, 1, 0 ]
+  up: [ 0, 1, 0 ]
+";
+
+  fn scene(items: &str) -> Scene {
+    Scene::parse(&format!("{}{}", CAMERA, items)).unwrap()
+  }
+
+  fn error(items: &str) -> SceneError {
+    Scene::parse(&format!("{}{}", CAMERA, items)).unwrap_err()
+  }
+
+  #[test]
+  fn test_camera_and_light() {
+    let s = scene("- add: light\n  at: [ 50, 100, -50 ]\n  intensity: [ 1, 1, 1 ]\n");
+
+    assert_eq!(s.camera().hsize(), 100);
+    assert_eq!(s.camera().vsize(), 50);
+    assert!(float::eq(s.camera().field_of_view(), 0.785));
+    assert_eq!(*s.camera().transform().matrix(), Mat4::view_transform(
+      Tuple::point(0.0, 1.5, -5.0), Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0)));
+    assert_eq!(s.world().lights().len(), 1);
+    assert_eq!(s.world().lights()[0].position(), Tuple::point(50.0, 100.0, -50.0));
+  }
+
+  #[test]
+  fn test_shape_with_material_and_transform() {
+    let s = scene("\
+- add: sphere
+  material:
+    color: [ 1, 0.5, 0.2 ]
+    diffuse: 0.7
+    refractive-index: 1.5
+  transform:
+    - [ scale, 2, 2, 2 ]
+    - [ translate, 1, 0, 0 ]
+");
+    let sphere = &s.world().objects()[0];
+
+    assert_eq!(sphere.material().color(), Color::new(1.0, 0.5, 0.2));
+    assert!(float::eq(sphere.material().diffuse(), 0.7));
+    assert!(float::eq(sphere.material().refractive_index(), 1.5));
+    assert_eq!(*sphere.transform().matrix(), Mat4::translation(1.0, 0.0, 0.0) * Mat4::scaling(2.0, 2.0, 2.0));
+  }
+
+  #[test]
+  fn test_defines_and_extend() {
+    let s = scene("\
+- define: white-material
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+- define: blue-material
+  extend: white-material
+  value:
+    color: [ 0.5, 0.8, 0.9 ]
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+- define: large-object
+  value:
+    - standard-transform
+    - [ scale, 3.5, 3.5, 3.5 ]
+- add: cube
+  material: blue-material
+  transform:
+    - large-object
+    - [ rotate-y, 1.5707964 ]
+");
+    let cube = &s.world().objects()[0];
+    let expected = Mat4::identity()
+      .translate(1.0, -1.0, 1.0)
+      .scale(0.5, 0.5, 0.5)
+      .scale(3.5, 3.5, 3.5)
+      .rotate_y(PI / 2.0);
+
+    assert_eq!(cube.material().color(), Color::new(0.5, 0.8, 0.9));
+    assert!(float::eq(cube.material().diffuse(), 0.7));
+    assert_eq!(*cube.transform().matrix(), expected);
+  }
+
+  #[test]
+  fn test_defined_shape() {
+    let s = scene("\
+- define: pillar
+  value:
+    add: cylinder
+    min: 0
+    max: 3
+    closed: true
+- add: pillar
+  transform:
+    - [ translate, 2, 0, 0 ]
+");
+    let pillar = &s.world().objects()[0];
+    let r = Ray::new(Tuple::point(2.0, 10.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+    let xs = pillar.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 7.0));
+  }
+
+  #[test]
+  fn test_pattern() {
+    let s = scene("\
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - [ 0, 0, 0 ]
+      transform:
+        - [ scale, 0.5, 0.5, 0.5 ]
+");
+    let pattern = s.world().objects()[0].material().pattern().unwrap();
+
+    assert_eq!(*pattern.transform().matrix(), Mat4::scaling(0.5, 0.5, 0.5));
+    assert_eq!(pattern.pattern_at(Tuple::point(1.2, 0.0, 0.0)), Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_group_and_csg() {
+    let s = scene("\
+- add: group
+  transform:
+    - [ translate, 0, 0, 5 ]
+  children:
+    - add: sphere
+    - add: csg
+      operation: difference
+      left:
+        add: cube
+      right: { add: sphere, transform: [ [ scale, 1.2, 1.2, 1.2 ] ] }
+");
+    let group = &s.world().objects()[0];
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+
+    // The cube minus the larger sphere leaves only its corners, so the ray
+    // only hits the plain sphere.
+    let xs = group.intersect(&r);
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 4.0));
+  }
+
+  #[test]
+  fn test_csg_material() {
+    let s = scene("\
+- add: csg
+  operation: union
+  left:
+    add: sphere
+  right:
+    add: cube
+  material:
+    ambient: 1
+");
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = s.world().objects()[0].intersect(&r);
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].object().material().ambient(), 1.0));
+  }
+
+  #[test]
+  fn test_missing_camera() {
+    let err = Scene::parse("- add: sphere\n").unwrap_err();
+    assert_eq!(err.line(), Some(1));
+    assert_eq!(err.to_string(), "line 1: the scene has no camera");
+  }
+
+  #[test]
+  fn test_errors_have_line_numbers() {
+    let examples = [
+      ("- add: sphere\n  colour: [1, 0, 0]\n", 9, "unexpected key \"colour\""),
+      ("- add: teapot\n", 8, "\"teapot\" hasn't been defined"),
+      ("- add: cube\n  material: nope\n", 9, "\"nope\" hasn't been defined"),
+      ("- add: cube\n  transform:\n    - [ rotate-x, 1, 2 ]\n", 10, "expected 1 number"),
+      ("- add: cube\n  transform:\n    - [ twist, 1 ]\n", 10, "unknown transform \"twist\""),
+      ("- add: light\n  at: [ 1, 2 ]\n  intensity: [ 1, 1, 1 ]\n", 9, "expected 3 numbers"),
+      ("- add: light\n  intensity: [ 1, 1, 1 ]\n", 8, "missing \"at\""),
+      ("- add: cylinder\n  closed: maybe\n", 9, "expected true or false, found \"maybe\""),
+      ("- add: obj\n  file: /does/not/exist.obj\n", 9, ""),
+      ("- add: sphere\n  material: { color: [1, 0, 0 }\n", 9, ""),
+      ("- add: cube\n  transform:\n    - [ scale, 0, 1, 1 ]\n", 10, "transform can't be inverted"),
+    ];
+
+    for (items, line, message) in examples.iter() {
+      let err = error(items);
+      assert_eq!(err.line(), Some(*line), "{}", err);
+      assert!(err.to_string().ends_with(message), "{}", err);
+    }
+  }
+
+  #[test]
+  fn test_tiny_transforms_are_invertible() {
+    let s = scene("- add: sphere\n  transform:\n    - [ scale, 0.01, 0.01, 0.01 ]\n");
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = s.world().objects()[0].intersect(&r);
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 4.99));
+  }
+
+  #[test]
+  fn test_empty_camera() {
+    let err = Scene::parse(&CAMERA.replace("width: 100", "width: 0")).unwrap_err();
+    assert_eq!(err.to_string(), "line 2: expected at least 1 pixel");
+    let err = Scene::parse(&CAMERA.replace("height: 50", "height: 0")).unwrap_err();
+    assert_eq!(err.to_string(), "line 3: expected at least 1 pixel");
+  }
+
+  #[test]
+  fn test_recursive_definitions() {
+    let err = error("- define: a\n  value:\n    add: a\n- add: a\n");
+    assert_eq!(err.to_string(), "line 10: \"a\" is defined in terms of itself");
+
+    let err = error("- define: t\n  value: [ t ]\n- add: sphere\n  transform: [ t ]\n");
+    assert_eq!(err.to_string(), "line 9: \"t\" is defined in terms of itself");
+
+    // Each name may still be used more than once, just not inside itself.
+    let s = scene("- define: up\n  value:\n    - [ translate, 0, 1, 0 ]\n\
+                   - define: ball\n  value:\n    add: sphere\n    transform: [ up, up ]\n\
+                   - add: group\n  children:\n    - add: ball\n    - add: ball\n");
+    assert_eq!(s.world().objects().len(), 1);
+  }
+
+  #[test]
+  fn test_from_file_missing() {
+    let err = Scene::from_file("/does/not/exist.yml").unwrap_err();
+    assert!(err.line().is_none());
+  }
+}
@@ -0,0 +1,318 @@
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::intersection::{Intersection, Intersections};
+use super::bounds::BoundingBox;
+use std::fmt;
+use std::sync::Arc;
+
+// Anything that can be placed in a world. Implementors only deal with their
+// own object space: the provided intersect and normal_at move rays into object
+// space and normals back out to world space for them.
+pub trait Shape: fmt::Debug + Send + Sync {
+  fn transform(&self) -> &Transform;
+  fn set_transform(&mut self, transform: Mat4);
+  fn material(&self) -> &Material;
+  fn set_material(&mut self, material: Material);
+
+  // Set by whichever group the shape is added to; a group passes the change
+  // on to its own children.
+  fn parent(&self) -> Option<&Arc<Parent>>;
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>);
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+  fn local_normal_at(&self, point: Tuple) -> Tuple;
+
+  // The box around the shape in its own object space.
+  fn bounds(&self) -> BoundingBox;
+
+  fn parent_space_bounds(&self) -> BoundingBox {
+    self.bounds().transform(self.transform().matrix())
+  }
+
+  // Shapes that contain others split them into a bounding volume hierarchy,
+  // so that whole subtrees can be skipped when a ray misses their bounds.
+  // Anything without children has nothing to divide.
+  fn divide(&mut self, _threshold: usize) {}
+
+  // Shapes whose normal varies with where on the surface they were hit, rather
+  // than only with the point, override this to read the hit's u and v.
+  fn local_normal_at_hit(&self, point: Tuple, _hit: &Intersection<'_>) -> Tuple {
+    self.local_normal_at(point)
+  }
+
+  // Whether other is this shape or, for shapes that contain others, any shape
+  // inside it.
+  fn includes(&self, other: &dyn Shape) -> bool {
+    std::ptr::addr_eq(self as *const Self, other)
+  }
+
+  fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+    let local_ray = ray.transform(self.transform().inverse());
+    Intersections::new(self.local_intersect(&local_ray))
+  }
+
+  fn normal_at(&self, world_point: Tuple) -> Tuple {
+    let local_point = self.world_to_object(world_point);
+    let local_normal = self.local_normal_at(local_point);
+    self.normal_to_world(local_normal)
+  }
+
+  fn normal_at_hit(&self, world_point: Tuple, hit: &Intersection<'_>) -> Tuple {
+    let local_point = self.world_to_object(world_point);
+    let local_normal = self.local_normal_at_hit(local_point, hit);
+    self.normal_to_world(local_normal)
+  }
+
+  fn world_to_object(&self, point: Tuple) -> Tuple {
+    let point = match self.parent() {
+      Some(parent) => parent.world_to_object(point),
+      None => point,
+    };
+    *self.transform().inverse() * point
+  }
+
+  fn normal_to_world(&self, normal: Tuple) -> Tuple {
+    let normal = to_world(self.transform(), normal);
+    match self.parent() {
+      Some(parent) => parent.normal_to_world(normal),
+      None => normal,
+    }
+  }
+}
+
+fn to_world(transform: &Transform, normal: Tuple) -> Tuple {
+  let normal = *transform.inverse_transpose() * normal;
+  Tuple::vector(normal.x(), normal.y(), normal.z()).normalize()
+}
+
+// One link in the chain of transforms from a shape up to the root of the
+// group tree it belongs to.
+#[derive(Debug)]
+pub struct Parent {
+  transform: Transform,
+  parent: Option<Arc<Parent>>,
+}
+
+impl Parent {
+  pub fn new(transform: Transform, parent: Option<Arc<Parent>>) -> Parent {
+    Parent { transform, parent }
+  }
+
+  pub fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  pub fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  pub fn world_to_object(&self, point: Tuple) -> Tuple {
+    let point = match &self.parent {
+      Some(parent) => parent.world_to_object(point),
+      None => point,
+    };
+    *self.transform.inverse() * point
+  }
+
+  pub fn normal_to_world(&self, normal: Tuple) -> Tuple {
+    let normal = to_world(&self.transform, normal);
+    match &self.parent {
+      Some(parent) => parent.normal_to_world(normal),
+      None => normal,
+    }
+  }
+}
+
+// Shapes are compared by identity rather than by value.
+pub fn same(a: &dyn Shape, b: &dyn Shape) -> bool {
+  std::ptr::addr_eq(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+  use std::f32::consts::{PI, FRAC_1_SQRT_2};
+
+  #[derive(Debug)]
+  struct TestShape {
+    transform: Transform,
+    material: Material,
+    parent: Option<Arc<Parent>>,
+    saved_ray: Mutex<Option<Ray>>,
+  }
+
+  impl TestShape {
+    fn new() -> TestShape {
+      TestShape {
+        transform: Transform::identity(),
+        material: Material::new(),
+        parent: None,
+        saved_ray: Mutex::new(None),
+      }
+    }
+
+    fn saved_ray(&self) -> Ray {
+      self.saved_ray.lock().unwrap().unwrap()
+    }
+  }
+
+  impl Shape for TestShape {
+    fn transform(&self) -> &Transform {
+      &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Mat4) {
+      self.transform = Transform::new(transform);
+    }
+
+    fn material(&self) -> &Material {
+      &self.material
+    }
+
+    fn set_material(&mut self, material: Material) {
+      self.material = material;
+    }
+
+    fn parent(&self) -> Option<&Arc<Parent>> {
+      self.parent.as_ref()
+    }
+
+    fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+      self.parent = parent;
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+      *self.saved_ray.lock().unwrap() = Some(*ray);
+      vec![]
+    }
+
+    fn local_normal_at(&self, point: Tuple) -> Tuple {
+      Tuple::vector(point.x(), point.y(), point.z())
+    }
+
+    fn bounds(&self) -> BoundingBox {
+      BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+    }
+  }
+
+  #[test]
+  fn test_default_transform_and_material() {
+    let s = TestShape::new();
+    assert_eq!(*s.transform().matrix(), Mat4::identity());
+    assert_eq!(*s.material(), Material::new());
+  }
+
+  #[test]
+  fn test_intersect_scaled() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    s.intersect(&r);
+
+    assert_eq!(s.saved_ray().origin(), Tuple::point(0.0, 0.0, -2.5));
+    assert_eq!(s.saved_ray().direction(), Tuple::vector(0.0, 0.0, 0.5));
+  }
+
+  #[test]
+  fn test_intersect_translated() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    s.intersect(&r);
+
+    assert_eq!(s.saved_ray().origin(), Tuple::point(-5.0, 0.0, -5.0));
+    assert_eq!(s.saved_ray().direction(), Tuple::vector(0.0, 0.0, 1.0));
+  }
+
+  #[test]
+  fn test_normal_at_translated() {
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::translation(0.0, 1.0, 0.0));
+    let n = s.normal_at(Tuple::point(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+
+    assert_eq!(n, Tuple::vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+  }
+
+  #[test]
+  fn test_normal_at_transformed() {
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::identity().rotate_z(PI / 5.0).scale(1.0, 0.5, 1.0));
+    let a = 2f32.sqrt() / 2.0;
+    let n = s.normal_at(Tuple::point(0.0, a, -a));
+
+    assert_eq!(n, Tuple::vector(0.0, 0.97014, -0.24254));
+  }
+
+  #[test]
+  fn test_includes_itself() {
+    let a = TestShape::new();
+    let b = TestShape::new();
+    assert!(a.includes(&a));
+    assert!(!a.includes(&b));
+  }
+
+  #[test]
+  fn test_parent_space_bounds() {
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::translation(1.0, -3.0, 5.0) * Mat4::scaling(0.5, 2.0, 4.0));
+    let b = s.parent_space_bounds();
+
+    assert_eq!(b.min(), Tuple::point(0.5, -5.0, 1.0));
+    assert_eq!(b.max(), Tuple::point(1.5, -1.0, 9.0));
+  }
+
+  #[test]
+  fn test_same() {
+    let a = TestShape::new();
+    let b = TestShape::new();
+    assert!(same(&a, &a));
+    assert!(!same(&a, &b));
+  }
+
+  // Outermost group rotated, inner group scaled, shape translated.
+  fn nested_shape() -> TestShape {
+    let g1 = Arc::new(Parent::new(Transform::new(Mat4::rotation_y(PI / 2.0)), None));
+    let g2 = Arc::new(Parent::new(Transform::new(Mat4::scaling(1.0, 2.0, 3.0)), Some(g1)));
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    s.set_parent(Some(g2));
+    s
+  }
+
+  #[test]
+  fn test_default_parent() {
+    assert!(TestShape::new().parent().is_none());
+  }
+
+  #[test]
+  fn test_world_to_object() {
+    let g1 = Arc::new(Parent::new(Transform::new(Mat4::rotation_y(PI / 2.0)), None));
+    let g2 = Arc::new(Parent::new(Transform::new(Mat4::scaling(2.0, 2.0, 2.0)), Some(g1)));
+    let mut s = TestShape::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    s.set_parent(Some(g2));
+
+    assert_eq!(s.world_to_object(Tuple::point(-2.0, 0.0, -10.0)), Tuple::point(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_normal_to_world() {
+    let s = nested_shape();
+    let a = 3f32.sqrt() / 3.0;
+    let n = s.normal_to_world(Tuple::vector(a, a, a));
+
+    assert_eq!(n, Tuple::vector(0.28571, 0.42857, -0.85714));
+  }
+
+  #[test]
+  fn test_normal_at_nested() {
+    let s = nested_shape();
+    let n = s.normal_at(Tuple::point(1.73205, 1.1547, -5.5774));
+
+    assert_eq!(n, Tuple::vector(0.28570, 0.42854, -0.85716));
+  }
+}
@@ -0,0 +1,179 @@
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use super::triangle::moller_trumbore;
+use std::sync::Arc;
+
+// A triangle with a normal at each corner. Normals are blended across the
+// surface using the u and v of the hit, which hides the facets of a mesh.
+#[derive(Debug, Clone)]
+pub struct SmoothTriangle {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  p1: Tuple,
+  p2: Tuple,
+  p3: Tuple,
+  n1: Tuple,
+  n2: Tuple,
+  n3: Tuple,
+  e1: Tuple,
+  e2: Tuple,
+}
+
+impl SmoothTriangle {
+  pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> SmoothTriangle {
+    SmoothTriangle {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      p1,
+      p2,
+      p3,
+      n1,
+      n2,
+      n3,
+      e1: p2 - p1,
+      e2: p3 - p1,
+    }
+  }
+
+  pub fn p1(&self) -> Tuple {
+    self.p1
+  }
+
+  pub fn p2(&self) -> Tuple {
+    self.p2
+  }
+
+  pub fn p3(&self) -> Tuple {
+    self.p3
+  }
+
+  pub fn n1(&self) -> Tuple {
+    self.n1
+  }
+
+  pub fn n2(&self) -> Tuple {
+    self.n2
+  }
+
+  pub fn n3(&self) -> Tuple {
+    self.n3
+  }
+}
+
+impl Shape for SmoothTriangle {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    match moller_trumbore(ray, self.p1, self.e1, self.e2) {
+      Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+      None => vec![],
+    }
+  }
+
+  // Without a hit there is nothing to interpolate with, so fall back to the
+  // normal of the flat triangle.
+  fn local_normal_at(&self, _point: Tuple) -> Tuple {
+    (self.e2 * self.e1).normalize()
+  }
+
+  fn local_normal_at_hit(&self, _point: Tuple, hit: &Intersection<'_>) -> Tuple {
+    self.n2 * hit.u() + self.n3 * hit.v() + self.n1 * (1.0 - hit.u() - hit.v())
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    let mut bounds = BoundingBox::empty();
+    bounds.add_point(self.p1);
+    bounds.add_point(self.p2);
+    bounds.add_point(self.p3);
+    bounds
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+  use super::super::intersection::Intersections;
+
+  fn triangle() -> SmoothTriangle {
+    SmoothTriangle::new(
+      Tuple::point(0.0, 1.0, 0.0),
+      Tuple::point(-1.0, 0.0, 0.0),
+      Tuple::point(1.0, 0.0, 0.0),
+      Tuple::vector(0.0, 1.0, 0.0),
+      Tuple::vector(-1.0, 0.0, 0.0),
+      Tuple::vector(1.0, 0.0, 0.0))
+  }
+
+  #[test]
+  fn test_new() {
+    let t = triangle();
+
+    assert_eq!(t.p1(), Tuple::point(0.0, 1.0, 0.0));
+    assert_eq!(t.p2(), Tuple::point(-1.0, 0.0, 0.0));
+    assert_eq!(t.p3(), Tuple::point(1.0, 0.0, 0.0));
+    assert_eq!(t.n1(), Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(t.n2(), Tuple::vector(-1.0, 0.0, 0.0));
+    assert_eq!(t.n3(), Tuple::vector(1.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_intersect_stores_uv() {
+    let t = triangle();
+    let r = Ray::new(Tuple::point(-0.2, 0.3, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = t.local_intersect(&r);
+
+    assert!(float::eq(xs[0].u(), 0.45));
+    assert!(float::eq(xs[0].v(), 0.25));
+  }
+
+  #[test]
+  fn test_normal_at_hit() {
+    let t = triangle();
+    let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+    let n = t.normal_at_hit(Tuple::point(0.0, 0.0, 0.0), &i);
+
+    assert_eq!(n, Tuple::vector(-0.5547, 0.83205, 0.0));
+  }
+
+  #[test]
+  fn test_prepare_computations() {
+    let t = triangle();
+    let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+    let r = Ray::new(Tuple::point(-0.2, 0.3, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = Intersections::new(vec![i]);
+    let comps = i.prepare_computations(&r, &xs);
+
+    assert_eq!(comps.normalv(), Tuple::vector(-0.5547, 0.83205, 0.0));
+  }
+}
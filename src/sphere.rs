@@ -0,0 +1,269 @@
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// A unit sphere centred on the origin of its own object space.
+#[derive(Debug, Clone)]
+pub struct Sphere {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+}
+
+impl Default for Sphere {
+  fn default() -> Sphere {
+    Sphere::new()
+  }
+}
+
+impl Sphere {
+  pub fn new() -> Sphere {
+    Sphere { transform: Transform::identity(), material: Material::new(), parent: None }
+  }
+}
+
+impl Shape for Sphere {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    let sphere_to_ray = ray.origin() - Tuple::point(0.0, 0.0, 0.0);
+
+    let a = ray.direction().dot(ray.direction());
+    let b = 2.0 * ray.direction().dot(sphere_to_ray);
+    let c = sphere_to_ray.dot(sphere_to_ray) - 1.0;
+
+    let discriminant = b.powi(2) - 4.0 * a * c;
+    if discriminant < 0.0 {
+      return vec![];
+    }
+
+    let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+    vec![Intersection::new(t1, self), Intersection::new(t2, self)]
+  }
+
+  fn local_normal_at(&self, point: Tuple) -> Tuple {
+    point - Tuple::point(0.0, 0.0, 0.0)
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    BoundingBox::new(Tuple::point(-1.0, -1.0, -1.0), Tuple::point(1.0, 1.0, 1.0))
+  }
+}
+
+// A sphere of glass, which plenty of refraction tests start from.
+#[cfg(test)]
+pub fn glass_sphere() -> Sphere {
+  let mut s = Sphere::new();
+  let mut m = Material::new();
+  m.set_transparency(1.0);
+  m.set_refractive_index(1.5);
+  s.set_material(m);
+  s
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+  use std::f32::consts::{PI, FRAC_1_SQRT_2};
+
+  #[test]
+  fn test_intersect_two_points() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 4.0));
+    assert!(float::eq(xs[1].t(), 6.0));
+  }
+
+  #[test]
+  fn test_intersect_tangent() {
+    let r = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 5.0));
+    assert!(float::eq(xs[1].t(), 5.0));
+  }
+
+  #[test]
+  fn test_intersect_miss() {
+    let r = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 0);
+  }
+
+  #[test]
+  fn test_intersect_from_inside() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), -1.0));
+    assert!(float::eq(xs[1].t(), 1.0));
+  }
+
+  #[test]
+  fn test_intersect_behind() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), -6.0));
+    assert!(float::eq(xs[1].t(), -4.0));
+  }
+
+  #[test]
+  fn test_intersect_sets_object() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let s = Sphere::new();
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(std::ptr::addr_eq(xs[0].object(), &s));
+    assert!(std::ptr::addr_eq(xs[1].object(), &s));
+  }
+
+  #[test]
+  fn test_normal_at() {
+    let s = Sphere::new();
+    let a = 3f32.sqrt() / 3.0;
+
+    assert_eq!(s.normal_at(Tuple::point(1.0, 0.0, 0.0)), Tuple::vector(1.0, 0.0, 0.0));
+    assert_eq!(s.normal_at(Tuple::point(0.0, 1.0, 0.0)), Tuple::vector(0.0, 1.0, 0.0));
+    assert_eq!(s.normal_at(Tuple::point(0.0, 0.0, 1.0)), Tuple::vector(0.0, 0.0, 1.0));
+    assert_eq!(s.normal_at(Tuple::point(a, a, a)), Tuple::vector(a, a, a));
+  }
+
+  #[test]
+  fn test_normal_is_normalized() {
+    let s = Sphere::new();
+    let a = 3f32.sqrt() / 3.0;
+    let n = s.normal_at(Tuple::point(a, a, a));
+
+    assert_eq!(n, n.normalize());
+  }
+
+  #[test]
+  fn test_default_transform() {
+    let s = Sphere::new();
+    assert_eq!(*s.transform().matrix(), Mat4::identity());
+  }
+
+  #[test]
+  fn test_set_transform() {
+    let mut s = Sphere::new();
+    let t = Mat4::translation(2.0, 3.0, 4.0);
+    s.set_transform(t);
+    assert_eq!(*s.transform().matrix(), t);
+    assert_eq!(*s.transform().inverse(), t.inverse());
+  }
+
+  #[test]
+  fn test_intersect_scaled() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::scaling(2.0, 2.0, 2.0));
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 2);
+    assert!(float::eq(xs[0].t(), 3.0));
+    assert!(float::eq(xs[1].t(), 7.0));
+  }
+
+  #[test]
+  fn test_intersect_translated() {
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(5.0, 0.0, 0.0));
+    let xs = s.intersect(&r);
+
+    assert_eq!(xs.len(), 0);
+  }
+
+  #[test]
+  fn test_normal_at_translated() {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::translation(0.0, 1.0, 0.0));
+    let n = s.normal_at(Tuple::point(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+
+    assert_eq!(n, Tuple::vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+  }
+
+  #[test]
+  fn test_normal_at_transformed() {
+    let mut s = Sphere::new();
+    s.set_transform(Mat4::identity().rotate_z(PI / 5.0).scale(1.0, 0.5, 1.0));
+    let a = 2f32.sqrt() / 2.0;
+    let n = s.normal_at(Tuple::point(0.0, a, -a));
+
+    assert_eq!(n, Tuple::vector(0.0, 0.97014, -0.24254));
+  }
+
+  #[test]
+  fn test_default_material() {
+    let s = Sphere::new();
+    assert_eq!(*s.material(), Material::new());
+  }
+
+  #[test]
+  fn test_set_material() {
+    let mut s = Sphere::new();
+    let mut m = Material::new();
+    m.set_ambient(1.0);
+    s.set_material(m.clone());
+    assert_eq!(*s.material(), m);
+  }
+
+  #[test]
+  fn test_glass_sphere() {
+    let s = glass_sphere();
+    assert_eq!(*s.transform().matrix(), Mat4::identity());
+    assert!(float::eq(s.material().transparency(), 1.0));
+    assert!(float::eq(s.material().refractive_index(), 1.5));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let b = Sphere::new().bounds();
+    assert_eq!(b.min(), Tuple::point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), Tuple::point(1.0, 1.0, 1.0));
+  }
+}
@@ -0,0 +1,260 @@
+use super::color::Color;
+use super::canvas::Canvas;
+use std::str::FromStr;
+
+// Squeezes unbounded radiance into the 0 to 1 range the 8-bit encoders can
+// store. Clamp is what the encoders do by themselves, so bright highlights
+// turn flat white; the others roll them off smoothly instead.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneOperator {
+  Clamp,
+  // L / (1 + L) on luminance, which never quite reaches white.
+  Reinhard,
+  // Like Reinhard, but luminance at or above white maps to 1.
+  ExtendedReinhard { white: f32 },
+  // Krzysztof Narkowicz's curve fit of the ACES filmic tone curve.
+  Aces,
+}
+
+// Parses clamp, reinhard, aces, or reinhard:<white> for the extended form.
+impl FromStr for ToneOperator {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<ToneOperator, String> {
+    match s {
+      "clamp" => Ok(ToneOperator::Clamp),
+      "reinhard" => Ok(ToneOperator::Reinhard),
+      "aces" => Ok(ToneOperator::Aces),
+      _ => match s.strip_prefix("reinhard:").map(str::parse::<f32>) {
+        Some(Ok(white)) if white > 0.0 => Ok(ToneOperator::ExtendedReinhard { white }),
+        Some(_) => Err(format!("white point in {:?} must be a positive number", s)),
+        None => Err(format!("unknown tone mapping operator {:?}", s)),
+      },
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMap {
+  operator: ToneOperator,
+  exposure: f32,
+}
+
+impl Default for ToneMap {
+  fn default() -> ToneMap {
+    ToneMap::new(ToneOperator::Clamp)
+  }
+}
+
+// Rec. 709 weights, matching the sRGB primaries colours are meant in.
+fn luminance(color: Color) -> f32 {
+  0.2126 * color.red() + 0.7152 * color.green() + 0.0722 * color.blue()
+}
+
+fn clamp(color: Color) -> Color {
+  Color::new(
+    color.red().clamp(0.0, 1.0),
+    color.green().clamp(0.0, 1.0),
+    color.blue().clamp(0.0, 1.0))
+}
+
+fn aces(x: f32) -> f32 {
+  // The fit expects its input pre-exposed by 0.6 to match the reference.
+  let x = x * 0.6;
+  (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+impl ToneMap {
+  pub fn new(operator: ToneOperator) -> ToneMap {
+    let mut tone_map = ToneMap { operator: ToneOperator::Clamp, exposure: 0.0 };
+    tone_map.set_operator(operator);
+    tone_map
+  }
+
+  pub fn operator(&self) -> ToneOperator {
+    self.operator
+  }
+
+  // In stops, so each +1 doubles the light reaching the operator.
+  pub fn exposure(&self) -> f32 {
+    self.exposure
+  }
+
+  pub fn set_operator(&mut self, operator: ToneOperator) {
+    if let ToneOperator::ExtendedReinhard { white } = operator {
+      assert!(white > 0.0, "white point must be positive");
+    }
+    self.operator = operator;
+  }
+
+  pub fn set_exposure(&mut self, stops: f32) {
+    assert!(stops.is_finite() && 2f32.powf(stops).is_finite(), "exposure must be a finite number of stops");
+    self.exposure = stops;
+  }
+
+  // The Reinhard operators scale the whole colour by the ratio the
+  // luminance was compressed by, which keeps hue as long as no channel ends
+  // up above 1. Every operator's result is clamped to 0 to 1, so saturated
+  // highlights, whose luminance is low next to their brightest channel,
+  // still clip in that channel.
+  pub fn apply(&self, color: Color) -> Color {
+    let color = color * 2f32.powf(self.exposure);
+
+    let mapped = match self.operator {
+      ToneOperator::Clamp => color,
+      ToneOperator::Reinhard => {
+        let l = luminance(color);
+        if l <= 0.0 {
+          return Color::new(0.0, 0.0, 0.0);
+        }
+        color * (1.0 / (1.0 + l))
+      },
+      ToneOperator::ExtendedReinhard { white } => {
+        let l = luminance(color);
+        if l <= 0.0 {
+          return Color::new(0.0, 0.0, 0.0);
+        }
+        color * ((1.0 + l / (white * white)) / (1.0 + l))
+      },
+      ToneOperator::Aces => Color::new(aces(color.red()), aces(color.green()), aces(color.blue())),
+    };
+
+    clamp(mapped)
+  }
+}
+
+impl Canvas {
+  pub fn tone_mapped(&self, tone_map: &ToneMap) -> Canvas {
+    let mut canvas = Canvas::new(self.width(), self.height());
+    for y in 0..self.height() {
+      for x in 0..self.width() {
+        canvas[(x, y)] = tone_map.apply(self[(x, y)]);
+      }
+    }
+    canvas
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::float;
+
+  #[test]
+  fn test_clamp() {
+    let tone_map = ToneMap::default();
+    assert_eq!(tone_map.operator(), ToneOperator::Clamp);
+    assert_eq!(tone_map.exposure(), 0.0);
+
+    let examples = [
+      (Color::new(0.25, 0.5, 0.75), Color::new(0.25, 0.5, 0.75)),
+      (Color::new(1.5, -0.5, 1.0), Color::new(1.0, 0.0, 1.0)),
+    ];
+    for (color, expected) in examples.iter() {
+      let mapped = tone_map.apply(*color);
+      assert_eq!(mapped, *expected);
+      assert_eq!([mapped.red_u8(), mapped.green_u8(), mapped.blue_u8()], [color.red_u8(), color.green_u8(), color.blue_u8()]);
+    }
+  }
+
+  #[test]
+  fn test_exposure() {
+    let mut tone_map = ToneMap::default();
+    tone_map.set_exposure(1.0);
+    assert_eq!(tone_map.apply(Color::new(0.25, 0.1, 0.0)), Color::new(0.5, 0.2, 0.0));
+    tone_map.set_exposure(-2.0);
+    assert_eq!(tone_map.apply(Color::new(2.0, 1.0, 4.0)), Color::new(0.5, 0.25, 1.0));
+  }
+
+  #[test]
+  #[should_panic(expected = "exposure must be a finite number of stops")]
+  fn test_exposure_must_be_finite() {
+    ToneMap::default().set_exposure(f32::NAN);
+  }
+
+  #[test]
+  #[should_panic(expected = "exposure must be a finite number of stops")]
+  fn test_exposure_must_not_overflow() {
+    ToneMap::default().set_exposure(200.0);
+  }
+
+  #[test]
+  fn test_reinhard() {
+    let tone_map = ToneMap::new(ToneOperator::Reinhard);
+    assert_eq!(tone_map.apply(Color::new(1.0, 1.0, 1.0)), Color::new(0.5, 0.5, 0.5));
+    assert_eq!(tone_map.apply(Color::new(3.0, 3.0, 3.0)), Color::new(0.75, 0.75, 0.75));
+    assert_eq!(tone_map.apply(Color::new(0.0, 0.0, 0.0)), Color::new(0.0, 0.0, 0.0));
+
+    // Hue is kept by scaling all channels together.
+    let mapped = tone_map.apply(Color::new(1.0, 0.5, 0.0));
+    assert!((mapped.red() / mapped.green() - 2.0).abs() < 1e-5);
+    assert!(mapped.red() < 1.0);
+  }
+
+  #[test]
+  fn test_reinhard_saturated_highlights_clip() {
+    let tone_map = ToneMap::new(ToneOperator::Reinhard);
+
+    // Luminance is only 2.126, so red is scaled to 10 / 3.126 before the clamp.
+    assert_eq!(tone_map.apply(Color::new(10.0, 0.0, 0.0)), Color::new(1.0, 0.0, 0.0));
+    let mapped = tone_map.apply(Color::new(10.0, 1.0, 0.0));
+    assert_eq!(mapped.red(), 1.0);
+    assert!(float::eq(mapped.green(), 1.0 / (1.0 + 2.126 + 0.7152)));
+  }
+
+  #[test]
+  fn test_extended_reinhard() {
+    let mut tone_map = ToneMap::default();
+    tone_map.set_operator(ToneOperator::ExtendedReinhard { white: 4.0 });
+    assert_eq!(tone_map.apply(Color::new(4.0, 4.0, 4.0)), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(tone_map.apply(Color::new(8.0, 8.0, 8.0)), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(tone_map.apply(Color::new(1.0, 1.0, 1.0)), Color::new(0.53125, 0.53125, 0.53125));
+
+    // An infinite white point is plain Reinhard.
+    tone_map.set_operator(ToneOperator::ExtendedReinhard { white: f32::INFINITY });
+    assert_eq!(tone_map.apply(Color::new(3.0, 3.0, 3.0)), Color::new(0.75, 0.75, 0.75));
+  }
+
+  #[test]
+  #[should_panic(expected = "white point must be positive")]
+  fn test_extended_reinhard_needs_white_point() {
+    ToneMap::default().set_operator(ToneOperator::ExtendedReinhard { white: 0.0 });
+  }
+
+  #[test]
+  fn test_aces() {
+    let tone_map = ToneMap::new(ToneOperator::Aces);
+    assert_eq!(tone_map.apply(Color::new(0.0, 0.0, 0.0)), Color::new(0.0, 0.0, 0.0));
+
+    let mut previous = 0.0;
+    for i in 1..100 {
+      let value = tone_map.apply(Color::new(i as f32 * 0.5, 0.0, 0.0)).red();
+      assert!(value > previous || value == 1.0);
+      previous = value;
+    }
+    assert!(tone_map.apply(Color::new(0.18, 0.18, 0.18)).red() > 0.1);
+    assert_eq!(tone_map.apply(Color::new(100.0, 100.0, 100.0)), Color::new(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_parse_operator() {
+    assert_eq!("clamp".parse(), Ok(ToneOperator::Clamp));
+    assert_eq!("reinhard".parse(), Ok(ToneOperator::Reinhard));
+    assert_eq!("reinhard:4".parse(), Ok(ToneOperator::ExtendedReinhard { white: 4.0 }));
+    assert_eq!("aces".parse(), Ok(ToneOperator::Aces));
+    assert_eq!("reinhard:0".parse::<ToneOperator>(), Err("white point in \"reinhard:0\" must be a positive number".to_string()));
+    assert_eq!("filmic".parse::<ToneOperator>(), Err("unknown tone mapping operator \"filmic\"".to_string()));
+  }
+
+  #[test]
+  fn test_tone_mapped() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas[(0, 0)] = Color::new(1.0, 1.0, 1.0);
+    canvas[(1, 0)] = Color::new(3.0, 3.0, 3.0);
+
+    let mapped = canvas.tone_mapped(&ToneMap::new(ToneOperator::Reinhard));
+    assert_eq!(mapped[(0, 0)], Color::new(0.5, 0.5, 0.5));
+    assert_eq!(mapped[(1, 0)], Color::new(0.75, 0.75, 0.75));
+    assert_eq!(canvas[(1, 0)], Color::new(3.0, 3.0, 3.0));
+  }
+}
@@ -0,0 +1,74 @@
+use super::matrix::Mat4;
+
+// A transformation matrix along with its inverse and inverse-transpose, which
+// are needed for every ray and normal. Mat4::inverse is expensive, so both are
+// computed once up front rather than on each use.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+  matrix: Mat4,
+  inverse: Mat4,
+  inverse_transpose: Mat4,
+}
+
+impl Transform {
+  pub fn new(matrix: Mat4) -> Transform {
+    let inverse = matrix.inverse();
+    Transform { matrix, inverse, inverse_transpose: inverse.transpose() }
+  }
+
+  pub fn identity() -> Transform {
+    Transform {
+      matrix: Mat4::identity(),
+      inverse: Mat4::identity(),
+      inverse_transpose: Mat4::identity(),
+    }
+  }
+
+  pub fn matrix(&self) -> &Mat4 {
+    &self.matrix
+  }
+
+  pub fn inverse(&self) -> &Mat4 {
+    &self.inverse
+  }
+
+  pub fn inverse_transpose(&self) -> &Mat4 {
+    &self.inverse_transpose
+  }
+}
+
+impl Default for Transform {
+  fn default() -> Transform {
+    Transform::identity()
+  }
+}
+
+impl From<Mat4> for Transform {
+  fn from(matrix: Mat4) -> Transform {
+    Transform::new(matrix)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_identity() {
+    let t = Transform::identity();
+    assert_eq!(*t.matrix(), Mat4::identity());
+    assert_eq!(*t.inverse(), Mat4::identity());
+    assert_eq!(*t.inverse_transpose(), Mat4::identity());
+  }
+
+  #[test]
+  fn test_new_caches_inverse() {
+    let m = Mat4::identity().scale(2.0, 4.0, 8.0).rotate_z(0.5).translate(1.0, 2.0, 3.0);
+    let t = Transform::new(m);
+
+    assert_eq!(*t.matrix(), m);
+    assert_eq!(*t.inverse(), m.inverse());
+    assert_eq!(*t.inverse_transpose(), m.inverse().transpose());
+    assert_eq!(*t.matrix() * *t.inverse(), Mat4::identity());
+  }
+}
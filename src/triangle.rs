@@ -0,0 +1,213 @@
+use super::float;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::matrix::Mat4;
+use super::transform::Transform;
+use super::material::Material;
+use super::shape::{Shape, Parent};
+use super::intersection::Intersection;
+use super::bounds::BoundingBox;
+use std::sync::Arc;
+
+// A flat triangle between three points. The edges and normal are worked out
+// up front since every intersection needs them.
+#[derive(Debug, Clone)]
+pub struct Triangle {
+  transform: Transform,
+  material: Material,
+  parent: Option<Arc<Parent>>,
+  p1: Tuple,
+  p2: Tuple,
+  p3: Tuple,
+  e1: Tuple,
+  e2: Tuple,
+  normal: Tuple,
+}
+
+impl Triangle {
+  pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Triangle {
+    let e1 = p2 - p1;
+    let e2 = p3 - p1;
+    Triangle {
+      transform: Transform::identity(),
+      material: Material::new(),
+      parent: None,
+      p1,
+      p2,
+      p3,
+      e1,
+      e2,
+      normal: (e2 * e1).normalize(),
+    }
+  }
+
+  pub fn p1(&self) -> Tuple {
+    self.p1
+  }
+
+  pub fn p2(&self) -> Tuple {
+    self.p2
+  }
+
+  pub fn p3(&self) -> Tuple {
+    self.p3
+  }
+
+  pub fn e1(&self) -> Tuple {
+    self.e1
+  }
+
+  pub fn e2(&self) -> Tuple {
+    self.e2
+  }
+
+  pub fn normal(&self) -> Tuple {
+    self.normal
+  }
+}
+
+// Möller–Trumbore: returns t along with the u and v of the hit relative to
+// p2 and p3, or None if the ray misses or runs parallel to the triangle.
+pub fn moller_trumbore(ray: &Ray, p1: Tuple, e1: Tuple, e2: Tuple) -> Option<(f32, f32, f32)> {
+  let dir_cross_e2 = ray.direction() * e2;
+  let det = e1.dot(dir_cross_e2);
+  if det.abs() < float::EPSILON {
+    return None;
+  }
+
+  let f = 1.0 / det;
+  let p1_to_origin = ray.origin() - p1;
+  let u = f * p1_to_origin.dot(dir_cross_e2);
+  if !(0.0..=1.0).contains(&u) {
+    return None;
+  }
+
+  let origin_cross_e1 = p1_to_origin * e1;
+  let v = f * ray.direction().dot(origin_cross_e1);
+  if v < 0.0 || u + v > 1.0 {
+    return None;
+  }
+
+  Some((f * e2.dot(origin_cross_e1), u, v))
+}
+
+impl Shape for Triangle {
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Mat4) {
+    self.transform = Transform::new(transform);
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn set_material(&mut self, material: Material) {
+    self.material = material;
+  }
+
+  fn parent(&self) -> Option<&Arc<Parent>> {
+    self.parent.as_ref()
+  }
+
+  fn set_parent(&mut self, parent: Option<Arc<Parent>>) {
+    self.parent = parent;
+  }
+
+  fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+    match moller_trumbore(ray, self.p1, self.e1, self.e2) {
+      Some((t, _, _)) => vec![Intersection::new(t, self)],
+      None => vec![],
+    }
+  }
+
+  fn local_normal_at(&self, _point: Tuple) -> Tuple {
+    self.normal
+  }
+
+  fn bounds(&self) -> BoundingBox {
+    let mut bounds = BoundingBox::empty();
+    bounds.add_point(self.p1);
+    bounds.add_point(self.p2);
+    bounds.add_point(self.p3);
+    bounds
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn triangle() -> Triangle {
+    Triangle::new(Tuple::point(0.0, 1.0, 0.0), Tuple::point(-1.0, 0.0, 0.0), Tuple::point(1.0, 0.0, 0.0))
+  }
+
+  #[test]
+  fn test_new() {
+    let t = triangle();
+
+    assert_eq!(t.p1(), Tuple::point(0.0, 1.0, 0.0));
+    assert_eq!(t.p2(), Tuple::point(-1.0, 0.0, 0.0));
+    assert_eq!(t.p3(), Tuple::point(1.0, 0.0, 0.0));
+    assert_eq!(t.e1(), Tuple::vector(-1.0, -1.0, 0.0));
+    assert_eq!(t.e2(), Tuple::vector(1.0, -1.0, 0.0));
+    assert_eq!(t.normal(), Tuple::vector(0.0, 0.0, -1.0));
+  }
+
+  #[test]
+  fn test_normal_at() {
+    let t = triangle();
+    let examples = [
+      Tuple::point(0.0, 0.5, 0.0),
+      Tuple::point(-0.5, 0.75, 0.0),
+      Tuple::point(0.5, 0.25, 0.0),
+    ];
+
+    for point in examples.iter() {
+      assert_eq!(t.local_normal_at(*point), t.normal());
+    }
+  }
+
+  #[test]
+  fn test_intersect_parallel() {
+    let t = triangle();
+    let r = Ray::new(Tuple::point(0.0, -1.0, -2.0), Tuple::vector(0.0, 1.0, 0.0));
+    assert!(t.local_intersect(&r).is_empty());
+  }
+
+  #[test]
+  fn test_intersect_miss_edges() {
+    let t = triangle();
+    let examples = [
+      Tuple::point(1.0, 1.0, -2.0),
+      Tuple::point(-1.0, 1.0, -2.0),
+      Tuple::point(0.0, -1.0, -2.0),
+    ];
+
+    for origin in examples.iter() {
+      let r = Ray::new(*origin, Tuple::vector(0.0, 0.0, 1.0));
+      assert!(t.local_intersect(&r).is_empty());
+    }
+  }
+
+  #[test]
+  fn test_intersect_hit() {
+    let t = triangle();
+    let r = Ray::new(Tuple::point(0.0, 0.5, -2.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = t.local_intersect(&r);
+
+    assert_eq!(xs.len(), 1);
+    assert!(float::eq(xs[0].t(), 2.0));
+  }
+
+  #[test]
+  fn test_bounds() {
+    let t = Triangle::new(Tuple::point(-3.0, 7.0, 2.0), Tuple::point(6.0, 2.0, -4.0), Tuple::point(2.0, -1.0, -1.0));
+    let b = t.bounds();
+
+    assert_eq!(b.min(), Tuple::point(-3.0, -1.0, -4.0));
+    assert_eq!(b.max(), Tuple::point(6.0, 7.0, 2.0));
+  }
+}
@@ -1,7 +1,6 @@
 use super::float;
 use std::ops;
 use std::fmt;
-use std::prelude::*;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Tuple {
@@ -68,6 +67,10 @@ impl Tuple {
   pub fn dot(&self, other: Tuple) -> f32 {
     self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
   }
+
+  pub fn reflect(&self, normal: Tuple) -> Tuple {
+    *self - normal * 2.0 * self.dot(normal)
+  }
 }
 
 impl PartialEq for Tuple {
@@ -234,8 +237,8 @@ mod tests {
     assert!(float::eq(Tuple::vector(1.0, 0.0, 0.0).magnitude(), 1.0));
     assert!(float::eq(Tuple::vector(0.0, 1.0, 0.0).magnitude(), 1.0));
     assert!(float::eq(Tuple::vector(0.0, 0.0, 1.0).magnitude(), 1.0));
-    assert!(float::eq(Tuple::vector(1.0, 2.0, 3.0).magnitude(), (14.0 as f32).sqrt()));
-    assert!(float::eq(Tuple::vector(-1.0, -2.0, -3.0).magnitude(), (14.0 as f32).sqrt()));
+    assert!(float::eq(Tuple::vector(1.0, 2.0, 3.0).magnitude(), 14.0_f32.sqrt()));
+    assert!(float::eq(Tuple::vector(-1.0, -2.0, -3.0).magnitude(), 14.0_f32.sqrt()));
   }
 
   #[test]
@@ -260,4 +263,19 @@ mod tests {
     assert!(a * b == result);
     assert!(b * a == -result);
   }
+
+  #[test]
+  fn test_reflect_45_degrees() {
+    let v = Tuple::vector(1.0, -1.0, 0.0);
+    let n = Tuple::vector(0.0, 1.0, 0.0);
+    assert_eq!(v.reflect(n), Tuple::vector(1.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_reflect_slanted() {
+    let v = Tuple::vector(0.0, -1.0, 0.0);
+    let a = 2f32.sqrt() / 2.0;
+    let n = Tuple::vector(a, a, 0.0);
+    assert_eq!(v.reflect(n), Tuple::vector(1.0, 0.0, 0.0));
+  }
 }
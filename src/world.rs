@@ -0,0 +1,551 @@
+use super::float;
+use super::color::Color;
+use super::tuple::Tuple;
+use super::ray::Ray;
+use super::shape::Shape;
+use super::light::{self, PointLight};
+use super::intersection::{Intersections, Computations};
+
+// How many times a ray may bounce between reflective surfaces before giving up.
+pub const DEFAULT_MAX_DEPTH: u32 = 5;
+
+#[derive(Debug)]
+pub struct World {
+  objects: Vec<Box<dyn Shape>>,
+  lights: Vec<PointLight>,
+  max_depth: u32,
+}
+
+impl Default for World {
+  fn default() -> World {
+    World::new()
+  }
+}
+
+impl World {
+  pub fn new() -> World {
+    World { objects: Vec::new(), lights: Vec::new(), max_depth: DEFAULT_MAX_DEPTH }
+  }
+
+  pub fn max_depth(&self) -> u32 {
+    self.max_depth
+  }
+
+  pub fn set_max_depth(&mut self, max_depth: u32) {
+    self.max_depth = max_depth;
+  }
+
+  pub fn objects(&self) -> &[Box<dyn Shape>] {
+    &self.objects
+  }
+
+  pub fn objects_mut(&mut self) -> &mut [Box<dyn Shape>] {
+    &mut self.objects
+  }
+
+  pub fn lights(&self) -> &[PointLight] {
+    &self.lights
+  }
+
+  pub fn add_object(&mut self, object: Box<dyn Shape>) {
+    self.objects.push(object);
+  }
+
+  pub fn add_light(&mut self, light: PointLight) {
+    self.lights.push(light);
+  }
+
+  pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+    let mut xs = Vec::new();
+    for object in &self.objects {
+      xs.extend(object.intersect(ray));
+    }
+    Intersections::new(xs)
+  }
+
+  pub fn shade_hit(&self, comps: &Computations, remaining: u32) -> Color {
+    let mut surface = Color::new(0.0, 0.0, 0.0);
+    for light in &self.lights {
+      surface = surface + light::lighting(
+        comps.object().material(),
+        comps.object(),
+        light,
+        comps.point(),
+        comps.eyev(),
+        comps.normalv(),
+        self.is_shadowed(light, comps.over_point()));
+    }
+
+    let reflected = self.reflected_color(comps, remaining);
+    let refracted = self.refracted_color(comps, remaining);
+
+    let material = comps.object().material();
+    if material.reflective() > 0.0 && material.transparency() > 0.0 {
+      let reflectance = comps.schlick();
+      surface + reflected * reflectance + refracted * (1.0 - reflectance)
+    } else {
+      surface + reflected + refracted
+    }
+  }
+
+  // Whether anything sits between the point and the light.
+  pub fn is_shadowed(&self, light: &PointLight, point: Tuple) -> bool {
+    let v = light.position() - point;
+    let distance = v.magnitude();
+    let ray = Ray::new(point, v.normalize());
+
+    match self.intersect(&ray).hit() {
+      Some(hit) => hit.t() < distance,
+      None => false,
+    }
+  }
+
+  // Each bounce uses up one of the remaining levels, so mirrors facing each
+  // other terminate instead of recursing forever.
+  pub fn reflected_color(&self, comps: &Computations, remaining: u32) -> Color {
+    let reflective = comps.object().material().reflective();
+    if remaining == 0 || float::eq(reflective, 0.0) {
+      return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let reflect_ray = Ray::new(comps.over_point(), comps.reflectv());
+    self.color_at(&reflect_ray, remaining - 1) * reflective
+  }
+
+  pub fn refracted_color(&self, comps: &Computations, remaining: u32) -> Color {
+    let transparency = comps.object().material().transparency();
+    if remaining == 0 || float::eq(transparency, 0.0) {
+      return Color::new(0.0, 0.0, 0.0);
+    }
+
+    // Snell's law, with cos_i taken from the eye vector and sin2_t solved for.
+    let n_ratio = comps.n1() / comps.n2();
+    let cos_i = comps.eyev().dot(comps.normalv());
+    let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+
+    // Past the critical angle all the light is reflected instead.
+    if sin2_t > 1.0 {
+      return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let direction = comps.normalv() * (n_ratio * cos_i - cos_t) - comps.eyev() * n_ratio;
+    let refract_ray = Ray::new(comps.under_point(), direction);
+
+    self.color_at(&refract_ray, remaining - 1) * transparency
+  }
+
+  pub fn color_at(&self, ray: &Ray, remaining: u32) -> Color {
+    let xs = self.intersect(ray);
+    match xs.hit() {
+      Some(hit) => self.shade_hit(&hit.prepare_computations(ray, &xs), remaining),
+      None => Color::new(0.0, 0.0, 0.0),
+    }
+  }
+}
+
+// The two concentric spheres and single light that the book's tests assume.
+#[cfg(test)]
+pub fn default_world() -> World {
+  use super::matrix::Mat4;
+  use super::material::Material;
+  use super::sphere::Sphere;
+
+  let mut w = World::new();
+  w.add_light(PointLight::new(Tuple::point(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+
+  let mut s1 = Sphere::new();
+  let mut m = Material::new();
+  m.set_color(Color::new(0.8, 1.0, 0.6));
+  m.set_diffuse(0.7);
+  m.set_specular(0.2);
+  s1.set_material(m);
+  w.add_object(Box::new(s1));
+
+  let mut s2 = Sphere::new();
+  s2.set_transform(Mat4::scaling(0.5, 0.5, 0.5));
+  w.add_object(Box::new(s2));
+
+  w
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::matrix::Mat4;
+  use super::super::plane::Plane;
+  use super::super::material::Material;
+  use super::super::sphere::Sphere;
+  use super::super::pattern;
+  use super::super::intersection::Intersection;
+
+  // The book computes these colours with an over_point offset of 0.0001, a
+  // tenth of OFFSET_EPSILON. Secondary rays starting further off the surface
+  // land far enough away to move the result in the fourth decimal place.
+  fn assert_color_near(actual: Color, expected: Color) {
+    let tolerance = 5e-4;
+    assert!(
+      (actual.red() - expected.red()).abs() < tolerance
+        && (actual.green() - expected.green()).abs() < tolerance
+        && (actual.blue() - expected.blue()).abs() < tolerance,
+      "{:?} is not within {} of {:?}", actual, tolerance, expected);
+  }
+
+  #[test]
+  fn test_new() {
+    let w = World::new();
+    assert!(w.objects().is_empty());
+    assert!(w.lights().is_empty());
+  }
+
+  #[test]
+  fn test_intersect() {
+    let w = default_world();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let xs = w.intersect(&r);
+
+    let ts: Vec<f32> = xs.iter().map(|i| i.t()).collect();
+    assert_eq!(ts.len(), 4);
+    assert!(float::eq(ts[0], 4.0));
+    assert!(float::eq(ts[1], 4.5));
+    assert!(float::eq(ts[2], 5.5));
+    assert!(float::eq(ts[3], 6.0));
+  }
+
+  #[test]
+  fn test_shade_hit() {
+    let w = default_world();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(4.0, w.objects()[0].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855));
+  }
+
+  #[test]
+  fn test_shade_hit_inside() {
+    let mut w = default_world();
+    w.lights = vec![PointLight::new(Tuple::point(0.0, 0.25, 0.0), Color::new(1.0, 1.0, 1.0))];
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(0.5, w.objects()[1].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.90498, 0.90498, 0.90498));
+  }
+
+  #[test]
+  fn test_shade_hit_multiple_lights() {
+    let mut w = default_world();
+    let light = w.lights()[0];
+    w.add_light(light);
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(4.0, w.objects()[0].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.76132, 0.95166, 0.571));
+  }
+
+  #[test]
+  fn test_color_at_miss() {
+    let w = default_world();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
+
+    assert_eq!(w.color_at(&r, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_color_at_hit() {
+    let w = default_world();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+
+    assert_eq!(w.color_at(&r, DEFAULT_MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855));
+  }
+
+  #[test]
+  fn test_color_at_behind_ray() {
+    let mut w = default_world();
+    for object in w.objects_mut() {
+      let mut m = object.material().clone();
+      m.set_ambient(1.0);
+      object.set_material(m);
+    }
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.75), Tuple::vector(0.0, 0.0, -1.0));
+
+    assert_eq!(w.color_at(&r, DEFAULT_MAX_DEPTH), w.objects()[1].material().color());
+  }
+
+  #[test]
+  fn test_not_shadowed_nothing_collinear() {
+    let w = default_world();
+    let p = Tuple::point(0.0, 10.0, 0.0);
+    assert!(!w.is_shadowed(&w.lights()[0], p));
+  }
+
+  #[test]
+  fn test_shadowed_object_between_point_and_light() {
+    let w = default_world();
+    let p = Tuple::point(10.0, -10.0, 10.0);
+    assert!(w.is_shadowed(&w.lights()[0], p));
+  }
+
+  #[test]
+  fn test_not_shadowed_object_behind_light() {
+    let w = default_world();
+    let p = Tuple::point(-20.0, 20.0, -20.0);
+    assert!(!w.is_shadowed(&w.lights()[0], p));
+  }
+
+  #[test]
+  fn test_not_shadowed_object_behind_point() {
+    let w = default_world();
+    let p = Tuple::point(-2.0, 2.0, -2.0);
+    assert!(!w.is_shadowed(&w.lights()[0], p));
+  }
+
+  #[test]
+  fn test_shade_hit_in_shadow() {
+    let mut w = World::new();
+    w.add_light(PointLight::new(Tuple::point(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+    w.add_object(Box::new(Sphere::new()));
+    let mut s2 = Sphere::new();
+    s2.set_transform(Mat4::translation(0.0, 0.0, 10.0));
+    w.add_object(Box::new(s2));
+
+    let r = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(4.0, w.objects()[1].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.1, 0.1, 0.1));
+  }
+
+  #[test]
+  fn test_new_max_depth() {
+    let mut w = World::new();
+    assert_eq!(w.max_depth(), DEFAULT_MAX_DEPTH);
+    w.set_max_depth(2);
+    assert_eq!(w.max_depth(), 2);
+  }
+
+  #[test]
+  fn test_reflected_color_nonreflective() {
+    let mut w = default_world();
+    let mut m = w.objects()[1].material().clone();
+    m.set_ambient(1.0);
+    w.objects_mut()[1].set_material(m);
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+    let i = Intersection::new(1.0, w.objects()[1].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+  }
+
+  fn add_reflective_plane(w: &mut World) {
+    let mut plane = Plane::new();
+    let mut m = Material::new();
+    m.set_reflective(0.5);
+    plane.set_material(m);
+    plane.set_transform(Mat4::translation(0.0, -1.0, 0.0));
+    w.add_object(Box::new(plane));
+  }
+
+  #[test]
+  fn test_reflected_color_reflective() {
+    let mut w = default_world();
+    add_reflective_plane(&mut w);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, -3.0), Tuple::vector(0.0, -a, a));
+    let i = Intersection::new(2f32.sqrt(), w.objects()[2].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_color_near(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.19032, 0.2379, 0.14274));
+  }
+
+  #[test]
+  fn test_shade_hit_reflective() {
+    let mut w = default_world();
+    add_reflective_plane(&mut w);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, -3.0), Tuple::vector(0.0, -a, a));
+    let i = Intersection::new(2f32.sqrt(), w.objects()[2].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_color_near(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.87677, 0.92436, 0.82918));
+  }
+
+  #[test]
+  fn test_color_at_mutually_reflective() {
+    let mut w = World::new();
+    w.add_light(PointLight::new(Tuple::point(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+
+    let mut m = Material::new();
+    m.set_reflective(1.0);
+
+    let mut lower = Plane::new();
+    lower.set_material(m.clone());
+    lower.set_transform(Mat4::translation(0.0, -1.0, 0.0));
+    w.add_object(Box::new(lower));
+
+    let mut upper = Plane::new();
+    upper.set_material(m);
+    upper.set_transform(Mat4::translation(0.0, 1.0, 0.0));
+    w.add_object(Box::new(upper));
+
+    // Terminating at all is the point of this test.
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+    w.color_at(&r, DEFAULT_MAX_DEPTH);
+  }
+
+  #[test]
+  fn test_reflected_color_at_max_depth() {
+    let mut w = default_world();
+    add_reflective_plane(&mut w);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, -3.0), Tuple::vector(0.0, -a, a));
+    let i = Intersection::new(2f32.sqrt(), w.objects()[2].as_ref());
+    let comps = i.prepare_computations(&r, &Intersections::new(vec![i]));
+
+    assert_eq!(w.reflected_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_refracted_color_opaque() {
+    let w = default_world();
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let shape = w.objects()[0].as_ref();
+    let xs = Intersections::new(vec![Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
+    let comps = xs[0].prepare_computations(&r, &xs);
+
+    assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+  }
+
+  fn make_first_glass(w: &mut World) {
+    let mut m = w.objects()[0].material().clone();
+    m.set_transparency(1.0);
+    m.set_refractive_index(1.5);
+    w.objects_mut()[0].set_material(m);
+  }
+
+  #[test]
+  fn test_refracted_color_at_max_depth() {
+    let mut w = default_world();
+    make_first_glass(&mut w);
+    let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+    let shape = w.objects()[0].as_ref();
+    let xs = Intersections::new(vec![Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
+    let comps = xs[0].prepare_computations(&r, &xs);
+
+    assert_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_refracted_color_total_internal_reflection() {
+    let mut w = default_world();
+    make_first_glass(&mut w);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, a), Tuple::vector(0.0, 1.0, 0.0));
+    let shape = w.objects()[0].as_ref();
+    let xs = Intersections::new(vec![Intersection::new(-a, shape), Intersection::new(a, shape)]);
+    let comps = xs[1].prepare_computations(&r, &xs);
+
+    assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_refracted_color() {
+    let mut w = default_world();
+
+    let mut m = w.objects()[0].material().clone();
+    m.set_ambient(1.0);
+    m.set_pattern(Box::new(pattern::test_pattern()));
+    w.objects_mut()[0].set_material(m);
+
+    let mut m = w.objects()[1].material().clone();
+    m.set_transparency(1.0);
+    m.set_refractive_index(1.5);
+    w.objects_mut()[1].set_material(m);
+
+    let r = Ray::new(Tuple::point(0.0, 0.0, 0.1), Tuple::vector(0.0, 1.0, 0.0));
+    let a = w.objects()[0].as_ref();
+    let b = w.objects()[1].as_ref();
+    let xs = Intersections::new(vec![
+      Intersection::new(-0.9899, a),
+      Intersection::new(-0.4899, b),
+      Intersection::new(0.4899, b),
+      Intersection::new(0.9899, a),
+    ]);
+    let comps = xs[2].prepare_computations(&r, &xs);
+
+    // under_point is OFFSET_EPSILON below the surface, so the refracted ray
+    // drifts from the book's the same way reflected rays do.
+    assert_color_near(w.refracted_color(&comps, 5), Color::new(0.0, 0.99888, 0.04725));
+  }
+
+  fn add_glass_floor_and_ball(w: &mut World, reflective: f32) {
+    let mut floor = Plane::new();
+    floor.set_transform(Mat4::translation(0.0, -1.0, 0.0));
+    let mut m = Material::new();
+    m.set_transparency(0.5);
+    m.set_refractive_index(1.5);
+    m.set_reflective(reflective);
+    floor.set_material(m);
+    w.add_object(Box::new(floor));
+
+    let mut ball = Sphere::new();
+    ball.set_transform(Mat4::translation(0.0, -3.5, -0.5));
+    let mut m = Material::new();
+    m.set_color(Color::new(1.0, 0.0, 0.0));
+    m.set_ambient(0.5);
+    ball.set_material(m);
+    w.add_object(Box::new(ball));
+  }
+
+  #[test]
+  fn test_shade_hit_transparent() {
+    let mut w = default_world();
+    add_glass_floor_and_ball(&mut w, 0.0);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, -3.0), Tuple::vector(0.0, -a, a));
+    let xs = Intersections::new(vec![Intersection::new(2f32.sqrt(), w.objects()[2].as_ref())]);
+    let comps = xs[0].prepare_computations(&r, &xs);
+
+    assert_eq!(w.shade_hit(&comps, 5), Color::new(0.93642, 0.68642, 0.68642));
+  }
+
+  #[test]
+  fn test_shade_hit_schlick() {
+    let mut w = default_world();
+    add_glass_floor_and_ball(&mut w, 0.5);
+    let a = 2f32.sqrt() / 2.0;
+    let r = Ray::new(Tuple::point(0.0, 0.0, -3.0), Tuple::vector(0.0, -a, a));
+    let xs = Intersections::new(vec![Intersection::new(2f32.sqrt(), w.objects()[2].as_ref())]);
+    let comps = xs[0].prepare_computations(&r, &xs);
+
+    assert_color_near(w.shade_hit(&comps, 5), Color::new(0.93391, 0.69643, 0.69243));
+  }
+
+  #[test]
+  fn test_no_shadow_acne() {
+    let mut w = World::new();
+    let light = PointLight::new(Tuple::point(-100.0, 100.0, -100.0), Color::new(1.0, 1.0, 1.0));
+    w.add_light(light);
+    let mut sphere = Sphere::new();
+    sphere.set_transform(Mat4::translation(3.0, 2.0, 1.0) * Mat4::scaling(10.0, 10.0, 10.0));
+    w.add_object(Box::new(sphere));
+
+    // Every point the light reaches directly must come out lit, however far
+    // the computed hit drifted off the surface.
+    for i in 0..20 {
+      for j in 0..20 {
+        let target = Tuple::point(i as f32 - 7.0, j as f32 - 8.0, -9.0);
+        let r = Ray::new(light.position(), (target - light.position()).normalize());
+        let xs = w.intersect(&r);
+        let hit = match xs.hit() {
+          Some(hit) => hit,
+          None => continue,
+        };
+        let comps = hit.prepare_computations(&r, &xs);
+        assert!(!w.is_shadowed(&light, comps.over_point()), "acne at {:?}", comps.point());
+      }
+    }
+  }
+}
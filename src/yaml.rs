@@ -0,0 +1,509 @@
+use std::fmt;
+
+// Just enough YAML for scene files: block mappings and sequences nested by
+// indentation, flow sequences and mappings like [1, 2, [3]] and { a: 1 },
+// quoted or plain scalars, and # comments. Anchors, multi-line strings and
+// multiple documents aren't supported.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YamlError {
+  line: usize,
+  message: String,
+}
+
+impl YamlError {
+  pub fn new(line: usize, message: impl Into<String>) -> YamlError {
+    YamlError { line, message: message.into() }
+  }
+
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+}
+
+impl fmt::Display for YamlError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for YamlError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Scalar(String),
+  Sequence(Vec<Node>),
+  Mapping(Vec<(String, Node)>),
+}
+
+// A value along with the line it started on, for error messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+  line: usize,
+  value: Value,
+}
+
+impl Node {
+  pub fn new(line: usize, value: Value) -> Node {
+    Node { line, value }
+  }
+
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  pub fn value(&self) -> &Value {
+    &self.value
+  }
+
+  pub fn as_str(&self) -> Result<&str, YamlError> {
+    match &self.value {
+      Value::Scalar(s) => Ok(s),
+      _ => Err(self.error("expected a single value")),
+    }
+  }
+
+  pub fn as_f32(&self) -> Result<f32, YamlError> {
+    let s = self.as_str()?;
+    s.parse().map_err(|_| self.error(format!("expected a number, found {:?}", s)))
+  }
+
+  pub fn as_bool(&self) -> Result<bool, YamlError> {
+    match self.as_str()? {
+      "true" => Ok(true),
+      "false" => Ok(false),
+      s => Err(self.error(format!("expected true or false, found {:?}", s))),
+    }
+  }
+
+  pub fn as_sequence(&self) -> Result<&[Node], YamlError> {
+    match &self.value {
+      Value::Sequence(items) => Ok(items),
+      _ => Err(self.error("expected a list")),
+    }
+  }
+
+  pub fn as_mapping(&self) -> Result<&[(String, Node)], YamlError> {
+    match &self.value {
+      Value::Mapping(entries) => Ok(entries),
+      _ => Err(self.error("expected a mapping")),
+    }
+  }
+
+  // The value for key if this is a mapping that has it.
+  pub fn get(&self, key: &str) -> Option<&Node> {
+    match &self.value {
+      Value::Mapping(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+
+  pub fn error(&self, message: impl Into<String>) -> YamlError {
+    YamlError::new(self.line, message)
+  }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Line<'a> {
+  number: usize,
+  indent: usize,
+  text: &'a str,
+}
+
+pub fn parse(input: &str) -> Result<Node, YamlError> {
+  let mut lines = Vec::new();
+  for (idx, raw) in input.lines().enumerate() {
+    let text = strip_comment(raw).trim_end();
+    let content = text.trim_start();
+    if content.is_empty() || content == "---" {
+      continue;
+    }
+    if text.starts_with('\t') {
+      return Err(YamlError::new(idx + 1, "tabs can't be used for indentation"));
+    }
+    lines.push(Line { number: idx + 1, indent: text.len() - content.len(), text: content });
+  }
+
+  if lines.is_empty() {
+    return Ok(Node::new(1, Value::Sequence(vec![])));
+  }
+
+  let mut parser = Parser { lines, pos: 0 };
+  let indent = parser.lines[0].indent;
+  let node = parser.parse_block(indent)?;
+
+  match parser.lines.get(parser.pos) {
+    Some(line) => Err(YamlError::new(line.number, "unexpected indentation")),
+    None => Ok(node),
+  }
+}
+
+// Drops everything from a # that isn't inside quotes and starts a word.
+fn strip_comment(line: &str) -> &str {
+  let mut quote = None;
+  let mut prev = ' ';
+  for (i, c) in line.char_indices() {
+    match quote {
+      Some(q) if c == q => quote = None,
+      Some(_) => {},
+      None if c == '"' || c == '\'' => quote = Some(c),
+      None if c == '#' && prev.is_whitespace() => return &line[..i],
+      None => {},
+    }
+    prev = c;
+  }
+  line
+}
+
+// Where the ': ' separating a key from its value is, ignoring any inside
+// brackets or quotes.
+fn find_key_separator(text: &str) -> Option<usize> {
+  let mut depth = 0;
+  let mut quote = None;
+  let bytes = text.as_bytes();
+  for (i, c) in text.char_indices() {
+    match quote {
+      Some(q) if c == q => quote = None,
+      Some(_) => {},
+      None => match c {
+        '"' | '\'' => quote = Some(c),
+        '[' | '{' => depth += 1,
+        ']' | '}' => depth -= 1,
+        ':' if depth == 0 && (i + 1 == bytes.len() || bytes[i + 1] == b' ') => return Some(i),
+        _ => {},
+      },
+    }
+  }
+  None
+}
+
+fn is_sequence_item(text: &str) -> bool {
+  text == "-" || text.starts_with("- ")
+}
+
+struct Parser<'a> {
+  lines: Vec<Line<'a>>,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn parse_block(&mut self, indent: usize) -> Result<Node, YamlError> {
+    let line = self.lines[self.pos];
+    if is_sequence_item(line.text) {
+      self.parse_sequence(indent)
+    } else if find_key_separator(line.text).is_some() {
+      self.parse_mapping(indent)
+    } else {
+      let node = parse_flow(line.text, line.number)?;
+      self.pos += 1;
+      Ok(node)
+    }
+  }
+
+  fn parse_sequence(&mut self, indent: usize) -> Result<Node, YamlError> {
+    let start = self.lines[self.pos].number;
+    let mut items = Vec::new();
+
+    while let Some(&line) = self.lines.get(self.pos) {
+      if line.indent != indent || !is_sequence_item(line.text) {
+        break;
+      }
+
+      let rest = line.text[1..].trim_start();
+      if rest.is_empty() {
+        self.pos += 1;
+        items.push(self.parse_nested(indent, line.number)?);
+      } else if is_sequence_item(rest) || find_key_separator(rest).is_some() {
+        // The item is a block that starts on the same line as its dash, so
+        // carry on as if it started on a line of its own.
+        let offset = line.text.len() - rest.len();
+        self.lines[self.pos] = Line { number: line.number, indent: indent + offset, text: rest };
+        items.push(self.parse_block(indent + offset)?);
+      } else {
+        items.push(parse_flow(rest, line.number)?);
+        self.pos += 1;
+      }
+    }
+
+    Ok(Node::new(start, Value::Sequence(items)))
+  }
+
+  fn parse_mapping(&mut self, indent: usize) -> Result<Node, YamlError> {
+    let start = self.lines[self.pos].number;
+    let mut entries: Vec<(String, Node)> = Vec::new();
+
+    while let Some(&line) = self.lines.get(self.pos) {
+      if line.indent != indent || is_sequence_item(line.text) {
+        break;
+      }
+
+      let sep = find_key_separator(line.text)
+        .ok_or_else(|| YamlError::new(line.number, format!("expected a key, found {:?}", line.text)))?;
+      let key = unquote(line.text[..sep].trim()).to_string();
+      let rest = line.text[sep + 1..].trim();
+      let number = line.number;
+
+      if entries.iter().any(|(k, _)| *k == key) {
+        return Err(YamlError::new(number, format!("duplicate key {:?}", key)));
+      }
+
+      let value = if rest.is_empty() {
+        self.pos += 1;
+        match self.lines.get(self.pos) {
+          // Sequences are allowed at the same indentation as their key.
+          Some(next) if next.indent == indent && is_sequence_item(next.text) => self.parse_sequence(indent)?,
+          _ => self.parse_nested(indent, number)?,
+        }
+      } else {
+        self.pos += 1;
+        parse_flow(rest, number)?
+      };
+      entries.push((key, value));
+    }
+
+    Ok(Node::new(start, Value::Mapping(entries)))
+  }
+
+  // The block indented under a key or dash, or an empty value if there isn't
+  // one.
+  fn parse_nested(&mut self, indent: usize, number: usize) -> Result<Node, YamlError> {
+    match self.lines.get(self.pos) {
+      Some(next) if next.indent > indent => {
+        let indent = next.indent;
+        self.parse_block(indent)
+      },
+      _ => Ok(Node::new(number, Value::Scalar(String::new()))),
+    }
+  }
+}
+
+fn unquote(s: &str) -> &str {
+  let quoted = s.len() >= 2 && (
+    (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')));
+  if quoted {
+    &s[1..s.len() - 1]
+  } else {
+    s
+  }
+}
+
+// Parses a value that fits on one line: a flow collection or a scalar.
+fn parse_flow(text: &str, line: usize) -> Result<Node, YamlError> {
+  if !text.starts_with('[') && !text.starts_with('{') {
+    return Ok(Node::new(line, Value::Scalar(unquote(text).to_string())));
+  }
+
+  let mut flow = Flow { chars: text.char_indices().peekable(), text, line };
+  let node = flow.parse_value()?;
+  flow.skip_whitespace();
+  match flow.chars.next() {
+    Some((_, c)) => Err(YamlError::new(line, format!("unexpected {:?} after value", c))),
+    None => Ok(node),
+  }
+}
+
+struct Flow<'a> {
+  chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+  text: &'a str,
+  line: usize,
+}
+
+impl<'a> Flow<'a> {
+  fn skip_whitespace(&mut self) {
+    while let Some((_, c)) = self.chars.peek() {
+      if !c.is_whitespace() {
+        break;
+      }
+      self.chars.next();
+    }
+  }
+
+  fn error(&self, message: impl Into<String>) -> YamlError {
+    YamlError::new(self.line, message)
+  }
+
+  fn parse_value(&mut self) -> Result<Node, YamlError> {
+    self.skip_whitespace();
+    match self.chars.peek() {
+      Some((_, '[')) => {
+        self.chars.next();
+        let items = self.parse_items(']', |flow| flow.parse_value())?;
+        Ok(Node::new(self.line, Value::Sequence(items)))
+      },
+      Some((_, '{')) => {
+        self.chars.next();
+        let entries = self.parse_items('}', |flow| {
+          let key = flow.parse_scalar()?;
+          flow.skip_whitespace();
+          match flow.chars.next() {
+            Some((_, ':')) => Ok((key, flow.parse_value()?)),
+            _ => Err(flow.error(format!("expected ':' after key {:?}", key))),
+          }
+        })?;
+        Ok(Node::new(self.line, Value::Mapping(entries)))
+      },
+      Some(_) => Ok(Node::new(self.line, Value::Scalar(self.parse_scalar()?))),
+      None => Err(self.error("unexpected end of line")),
+    }
+  }
+
+  // Comma-separated items up to the closing bracket, allowing a trailing comma.
+  fn parse_items<T>(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<T, YamlError>)
+    -> Result<Vec<T>, YamlError> {
+    let mut items = Vec::new();
+    loop {
+      self.skip_whitespace();
+      if let Some((_, c)) = self.chars.peek() {
+        if *c == close {
+          self.chars.next();
+          return Ok(items);
+        }
+      }
+
+      items.push(item(self)?);
+      self.skip_whitespace();
+      match self.chars.next() {
+        Some((_, ',')) => {},
+        Some((_, c)) if c == close => return Ok(items),
+        Some((_, c)) => return Err(self.error(format!("expected ',' or {:?}, found {:?}", close, c))),
+        None => return Err(self.error(format!("missing {:?}", close))),
+      }
+    }
+  }
+
+  fn parse_scalar(&mut self) -> Result<String, YamlError> {
+    self.skip_whitespace();
+    let start = match self.chars.peek() {
+      Some((i, _)) => *i,
+      None => return Err(self.error("unexpected end of line")),
+    };
+
+    if let Some((_, q)) = self.chars.peek().cloned().filter(|(_, c)| *c == '"' || *c == '\'') {
+      self.chars.next();
+      for (i, c) in self.chars.by_ref() {
+        if c == q {
+          return Ok(self.text[start + 1..i].to_string());
+        }
+      }
+      return Err(self.error("unterminated string"));
+    }
+
+    let mut end = self.text.len();
+    while let Some((i, c)) = self.chars.peek() {
+      if matches!(c, ',' | ']' | '}' | ':' | '[' | '{') {
+        end = *i;
+        break;
+      }
+      self.chars.next();
+    }
+    Ok(self.text[start..end].trim().to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scalar(node: &Node) -> &str {
+    node.as_str().unwrap()
+  }
+
+  #[test]
+  fn test_empty() {
+    assert_eq!(parse("# nothing\n\n").unwrap().as_sequence().unwrap().len(), 0);
+  }
+
+  #[test]
+  fn test_mapping() {
+    let doc = parse("a: 1\nb: hello world  # comment\n'c': \"quoted # not a comment\"\n").unwrap();
+
+    assert_eq!(scalar(doc.get("a").unwrap()), "1");
+    assert_eq!(scalar(doc.get("b").unwrap()), "hello world");
+    assert_eq!(scalar(doc.get("c").unwrap()), "quoted # not a comment");
+    assert!(doc.get("d").is_none());
+  }
+
+  #[test]
+  fn test_sequence_of_mappings() {
+    let doc = parse("- add: camera\n  width: 100\n\n- add: light\n  at: [ 50, 100, -50 ]\n").unwrap();
+    let items = doc.as_sequence().unwrap();
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(scalar(items[0].get("add").unwrap()), "camera");
+    assert_eq!(items[0].get("width").unwrap().as_f32().unwrap(), 100.0);
+    assert_eq!(items[1].line(), 4);
+
+    let at = items[1].get("at").unwrap().as_sequence().unwrap();
+    let at: Vec<f32> = at.iter().map(|n| n.as_f32().unwrap()).collect();
+    assert_eq!(at, vec![50.0, 100.0, -50.0]);
+  }
+
+  #[test]
+  fn test_nested_blocks() {
+    let input = "\
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+- add: group
+  children:
+  - add: sphere
+    material:
+      color: [1, 0, 0]
+  - add: cube
+";
+    let doc = parse(input).unwrap();
+    let items = doc.as_sequence().unwrap();
+
+    let value = items[0].get("value").unwrap().as_sequence().unwrap();
+    assert_eq!(value.len(), 2);
+    assert_eq!(scalar(&value[1].as_sequence().unwrap()[0]), "scale");
+    assert_eq!(value[1].line(), 4);
+
+    let children = items[1].get("children").unwrap().as_sequence().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(scalar(children[1].get("add").unwrap()), "cube");
+    assert!(children[0].get("material").unwrap().get("color").is_some());
+  }
+
+  #[test]
+  fn test_flow_collections() {
+    let doc = parse("a: [ [1, 2], { x: 'y, z', w: [] }, plain text ]\n").unwrap();
+    let a = doc.get("a").unwrap().as_sequence().unwrap();
+
+    assert_eq!(a.len(), 3);
+    assert_eq!(a[0].as_sequence().unwrap().len(), 2);
+    assert_eq!(scalar(a[1].get("x").unwrap()), "y, z");
+    assert!(a[1].get("w").unwrap().as_sequence().unwrap().is_empty());
+    assert_eq!(scalar(&a[2]), "plain text");
+  }
+
+  #[test]
+  fn test_errors_have_line_numbers() {
+    let examples = [
+      ("a: 1\nb: [1, 2\n", 2),
+      ("a: 1\na: 2\n", 2),
+      ("- a: 1\n  just text\n", 2),
+      ("a: 1\n    b: 2\n", 2),
+      ("a:\n\tb: 1\n", 2),
+      ("a: [1, 2] x\n", 1),
+    ];
+
+    for (input, line) in examples.iter() {
+      assert_eq!(parse(input).unwrap_err().line(), *line, "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn test_node_errors() {
+    let doc = parse("a: x\nb: [1]\n").unwrap();
+    let err = doc.get("a").unwrap().as_f32().unwrap_err();
+
+    assert_eq!(err.to_string(), "line 1: expected a number, found \"x\"");
+    assert_eq!(doc.get("b").unwrap().as_str().unwrap_err().line(), 2);
+  }
+}
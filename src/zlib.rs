@@ -0,0 +1,360 @@
+// Just enough of zlib (RFC 1950) and deflate (RFC 1951) to write PNGs. The
+// compressor finds repeats with hash chains and codes everything with the
+// fixed Huffman tables, which does well on rendered images without having to
+// build and store per-block trees.
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+  let mut table = [0u32; 256];
+  let mut n = 0;
+  while n < 256 {
+    let mut c = n as u32;
+    let mut k = 0;
+    while k < 8 {
+      c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+      k += 1;
+    }
+    table[n] = c;
+    n += 1;
+  }
+  table
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+  let mut crc = 0xffff_ffffu32;
+  for byte in data.iter() {
+    crc = CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+  }
+  crc ^ 0xffff_ffff
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+  const MOD: u32 = 65521;
+  // 5552 is the most bytes that can be summed before b could overflow.
+  let (mut a, mut b) = (1u32, 0u32);
+  for chunk in data.chunks(5552) {
+    for byte in chunk.iter() {
+      a += *byte as u32;
+      b += a;
+    }
+    a %= MOD;
+    b %= MOD;
+  }
+  (b << 16) | a
+}
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: usize = 15;
+// How many earlier positions to try before settling for the best match so
+// far. Longer chains compress slightly better but slow down flat images a lot.
+const MAX_CHAIN: usize = 128;
+
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+  35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+  3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+  7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+struct BitWriter {
+  out: Vec<u8>,
+  bits: u32,
+  count: u32,
+}
+
+impl BitWriter {
+  fn new(out: Vec<u8>) -> BitWriter {
+    BitWriter { out, bits: 0, count: 0 }
+  }
+
+  // Values go in least significant bit first.
+  fn write(&mut self, value: u32, count: u32) {
+    self.bits |= value << self.count;
+    self.count += count;
+    while self.count >= 8 {
+      self.out.push(self.bits as u8);
+      self.bits >>= 8;
+      self.count -= 8;
+    }
+  }
+
+  // Huffman codes go in most significant bit first.
+  fn write_code(&mut self, code: u32, count: u32) {
+    self.write(code.reverse_bits() >> (32 - count), count);
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    if self.count > 0 {
+      self.out.push(self.bits as u8);
+    }
+    self.out
+  }
+
+  fn write_literal(&mut self, symbol: u16) {
+    match symbol {
+      0..=143 => self.write_code(0x30 + symbol as u32, 8),
+      144..=255 => self.write_code(0x190 + (symbol as u32 - 144), 9),
+      256..=279 => self.write_code(symbol as u32 - 256, 7),
+      _ => self.write_code(0xc0 + (symbol as u32 - 280), 8),
+    }
+  }
+
+  fn write_match(&mut self, length: usize, distance: usize) {
+    let code = LENGTH_BASE.iter().rposition(|base| *base as usize <= length).unwrap();
+    self.write_literal(257 + code as u16);
+    self.write((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+
+    let code = DISTANCE_BASE.iter().rposition(|base| *base as usize <= distance).unwrap();
+    self.write_code(code as u32, 5);
+    self.write((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+  }
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+  let n = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+  (n.wrapping_mul(2_654_435_761) >> 16) & ((1 << HASH_BITS) - 1)
+}
+
+// Wraps data in a zlib stream holding a single fixed-Huffman deflate block.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+  // CMF says deflate with a 32K window, FLG is chosen so the pair is a
+  // multiple of 31.
+  let mut out = BitWriter::new(vec![0x78, 0x9c]);
+  out.write(1, 1); // BFINAL
+  out.write(1, 2); // BTYPE: fixed Huffman codes
+
+  // head holds the latest position for each hash and prev links each
+  // position to the one before it with the same hash, offset by one so zero
+  // can mean none.
+  let mut head = vec![0usize; 1 << HASH_BITS];
+  let mut prev = vec![0usize; WINDOW_SIZE];
+  let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+    let h = hash(data, i);
+    prev[i % WINDOW_SIZE] = head[h];
+    head[h] = i + 1;
+  };
+
+  let mut i = 0;
+  while i < data.len() {
+    let mut best_length = 0;
+    let mut best_distance = 0;
+
+    if i + MIN_MATCH <= data.len() {
+      let max_length = MAX_MATCH.min(data.len() - i);
+      let mut candidate = head[hash(data, i)];
+      let mut chain = 0;
+
+      while candidate > 0 && chain < MAX_CHAIN {
+        let j = candidate - 1;
+        if i - j > WINDOW_SIZE {
+          break;
+        }
+        if data[j + best_length] == data[i + best_length] {
+          let length = data[j..].iter().zip(data[i..i + max_length].iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+          if length > best_length {
+            best_length = length;
+            best_distance = i - j;
+            if length == max_length {
+              break;
+            }
+          }
+        }
+        // Windows get overwritten once positions are far enough back, and
+        // the link is then stale.
+        let next = prev[j % WINDOW_SIZE];
+        if next >= candidate {
+          break;
+        }
+        candidate = next;
+        chain += 1;
+      }
+    }
+
+    if best_length >= MIN_MATCH {
+      out.write_match(best_length, best_distance);
+      for k in i..i + best_length {
+        if k + MIN_MATCH <= data.len() {
+          insert(k, &mut head, &mut prev);
+        }
+      }
+      i += best_length;
+    } else {
+      out.write_literal(data[i] as u16);
+      if i + MIN_MATCH <= data.len() {
+        insert(i, &mut head, &mut prev);
+      }
+      i += 1;
+    }
+  }
+
+  out.write_literal(256);
+  let mut out = out.finish();
+  out.extend_from_slice(&adler32(data).to_be_bytes());
+  out
+}
+
+// Decodes what compress produces, and stored blocks, so tests can check the
+// round trip. Returns None for anything malformed or unsupported.
+#[cfg(test)]
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+  struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+  }
+
+  impl<'a> BitReader<'a> {
+    fn bits(&mut self, count: usize) -> Option<u32> {
+      let mut value = 0;
+      for n in 0..count {
+        let byte = *self.data.get(self.pos / 8)?;
+        value |= (((byte >> (self.pos % 8)) & 1) as u32) << n;
+        self.pos += 1;
+      }
+      Some(value)
+    }
+
+    fn code(&mut self, count: usize) -> Option<u32> {
+      let mut value = 0;
+      for _ in 0..count {
+        value = (value << 1) | self.bits(1)?;
+      }
+      Some(value)
+    }
+
+    fn literal(&mut self) -> Option<u16> {
+      let mut code = self.code(7)?;
+      if code <= 0x17 {
+        return Some(256 + code as u16);
+      }
+      code = (code << 1) | self.bits(1)?;
+      match code {
+        0x30..=0xbf => Some((code - 0x30) as u16),
+        0xc0..=0xc7 => Some((code - 0xc0 + 280) as u16),
+        _ => Some((((code << 1) | self.bits(1)?) - 0x190 + 144) as u16),
+      }
+    }
+  }
+
+  if data.len() < 6 || (data[0] as u16 * 256 + data[1] as u16) % 31 != 0 || data[0] & 0x0f != 8 {
+    return None;
+  }
+
+  let mut reader = BitReader { data: &data[..data.len() - 4], pos: 16 };
+  let mut out: Vec<u8> = vec![];
+  loop {
+    let last = reader.bits(1)?;
+    match reader.bits(2)? {
+      0 => {
+        reader.pos = reader.pos.div_ceil(8) * 8;
+        let length = reader.bits(16)? as usize;
+        reader.bits(16)?;
+        let start = reader.pos / 8;
+        out.extend_from_slice(reader.data.get(start..start + length)?);
+        reader.pos += length * 8;
+      },
+      1 => loop {
+        let symbol = reader.literal()?;
+        if symbol < 256 {
+          out.push(symbol as u8);
+          continue;
+        } else if symbol == 256 {
+          break;
+        }
+        let code = (symbol - 257) as usize;
+        let length = *LENGTH_BASE.get(code)? as usize + reader.bits(LENGTH_EXTRA[code] as usize)? as usize;
+        let code = reader.code(5)? as usize;
+        let distance = *DISTANCE_BASE.get(code)? as usize + reader.bits(DISTANCE_EXTRA[code] as usize)? as usize;
+        let start = out.len().checked_sub(distance)?;
+        for k in 0..length {
+          out.push(out[start + k]);
+        }
+      },
+      _ => return None,
+    }
+    if last == 1 {
+      break;
+    }
+  }
+
+  let checksum = u32::from_be_bytes([
+    data[data.len() - 4], data[data.len() - 3], data[data.len() - 2], data[data.len() - 1]]);
+  if checksum == adler32(&out) {
+    Some(out)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+  }
+
+  #[test]
+  fn test_adler32() {
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    assert_eq!(adler32(&[0xff; 100_000]), 0x149a_302c);
+  }
+
+  #[test]
+  fn test_compress_empty() {
+    assert_eq!(compress(b""), vec![0x78, 0x9c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    assert_eq!(decompress(&compress(b"")), Some(vec![]));
+  }
+
+  #[test]
+  fn test_compress_round_trip() {
+    let mut noise = Vec::with_capacity(100_000);
+    let mut seed = 1u32;
+    for _ in 0..100_000 {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+      noise.push((seed >> 16) as u8);
+    }
+
+    let examples: [&[u8]; 5] = [
+      b"a",
+      b"abcabcabcabcabcabcabcabc",
+      &[0; 70_000],
+      &noise,
+      &[noise.as_slice(), noise.as_slice()].concat(),
+    ];
+
+    for data in examples.iter() {
+      assert_eq!(decompress(&compress(data)).as_deref(), Some(*data));
+    }
+  }
+
+  #[test]
+  fn test_compress_shrinks_repeats() {
+    assert!(compress(&[7; 100_000]).len() < 1000);
+  }
+
+  #[test]
+  fn test_decompress_checks_adler32() {
+    let mut data = compress(b"hello");
+    *data.last_mut().unwrap() ^= 1;
+    assert_eq!(decompress(&data), None);
+  }
+}